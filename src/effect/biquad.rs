@@ -0,0 +1,209 @@
+use super::Effect;
+use crate::{poly_sample, PolySample, SampleTiming};
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BiquadType {
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+    AllPass,
+    LowShelf,
+    HighShelf,
+    Peaking,
+}
+
+/// Second order filter with the responses of the RBJ Audio EQ Cookbook.
+///
+/// `cutoff`, `q` and `gain` can be changed every sample, they are smoothed internally to prevent zipper noise.
+#[derive(Clone)]
+pub struct Biquad {
+    pub filter_type: BiquadType,
+    /// Cutoff or center frequency in Hz.
+    pub cutoff: f32,
+    pub q: f32,
+    /// Gain in dB, only used by [`LowShelf`](BiquadType::LowShelf),
+    /// [`HighShelf`](BiquadType::HighShelf) and [`Peaking`](BiquadType::Peaking).
+    pub gain: f32,
+    /// Time (in seconds) it takes parameter changes to mostly take effect.
+    pub smoothing: f32,
+    smoothed_parameters: Option<(f32, f32, f32)>,
+    //filter type and sample rate the coefficients were calculated for
+    coefficients_for: Option<(BiquadType, f32)>,
+    coefficients: [f32; 5],
+    states: Vec<[f32; 2]>,
+}
+
+impl Biquad {
+    pub fn new(filter_type: BiquadType, cutoff: f32, q: f32, gain: f32) -> Self {
+        Self {
+            filter_type,
+            cutoff,
+            q,
+            gain,
+            smoothing: 0.005,
+            smoothed_parameters: None,
+            coefficients_for: None,
+            coefficients: [1.0, 0.0, 0.0, 0.0, 0.0],
+            states: Vec::new(),
+        }
+    }
+
+    pub fn low_pass(cutoff: f32, q: f32) -> Self {
+        Self::new(BiquadType::LowPass, cutoff, q, 0.0)
+    }
+
+    pub fn high_pass(cutoff: f32, q: f32) -> Self {
+        Self::new(BiquadType::HighPass, cutoff, q, 0.0)
+    }
+
+    /// Clears the filter state of all channels.
+    pub fn reset(&mut self) {
+        self.states.clear();
+        self.smoothed_parameters = None;
+    }
+
+    fn target_parameters(&self) -> (f32, f32, f32) {
+        (self.cutoff.max(1.0).log2(), self.q.max(0.01), self.gain)
+    }
+
+    fn update_coefficients(&mut self, sample_rate: f32) {
+        let target = self.target_parameters();
+        let (cutoff, q, gain) = match self.smoothed_parameters {
+            Some((cutoff, q, gain)) if self.smoothing > 0.0 => {
+                let factor = 1.0 - (-1.0 / (self.smoothing * sample_rate)).exp();
+                (
+                    cutoff + (target.0 - cutoff) * factor,
+                    q + (target.1 - q) * factor,
+                    gain + (target.2 - gain) * factor,
+                )
+            }
+            _ => target,
+        };
+        let coefficients_for = Some((self.filter_type, sample_rate));
+        if self.smoothed_parameters == Some((cutoff, q, gain))
+            && self.coefficients_for == coefficients_for
+        {
+            return;
+        }
+        self.smoothed_parameters = Some((cutoff, q, gain));
+        self.coefficients_for = coefficients_for;
+
+        let cutoff = cutoff.exp2().min(sample_rate * 0.49);
+        let w0 = 2.0 * PI * cutoff / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        let a = 10.0f32.powf(gain / 40.0);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        let [b0, b1, b2, a0, a1, a2] = match self.filter_type {
+            BiquadType::LowPass => [
+                (1.0 - cos) / 2.0,
+                1.0 - cos,
+                (1.0 - cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ],
+            BiquadType::HighPass => [
+                (1.0 + cos) / 2.0,
+                -(1.0 + cos),
+                (1.0 + cos) / 2.0,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ],
+            BiquadType::BandPass => [alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            BiquadType::Notch => [1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+            BiquadType::AllPass => {
+                [1.0 - alpha, -2.0 * cos, 1.0 + alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha]
+            }
+            BiquadType::Peaking => [
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ],
+            BiquadType::LowShelf => [
+                a * ((a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha),
+                (a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha,
+            ],
+            BiquadType::HighShelf => [
+                a * ((a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha),
+                (a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha,
+            ],
+        };
+        self.coefficients = [b0 / a0, b1 / a0, b2 / a0, a1 / a0, a2 / a0];
+    }
+}
+
+impl Default for Biquad {
+    fn default() -> Self {
+        Self::low_pass(1000.0, std::f32::consts::FRAC_1_SQRT_2)
+    }
+}
+
+impl Effect for Biquad {
+    fn process(&mut self, sample_timing: &SampleTiming, mut poly_sample: PolySample) -> PolySample {
+        self.update_coefficients(sample_timing.sample_rate);
+        if self.states.len() < poly_sample.len() {
+            self.states.resize(poly_sample.len(), [0.0; 2]);
+        }
+
+        let [b0, b1, b2, a1, a2] = self.coefficients;
+        //transposed direct form II
+        for (sample, state) in poly_sample.iter_mut().zip(self.states.iter_mut()) {
+            let input = *sample;
+            let output = b0 * input + state[0];
+            state[0] = b1 * input - a1 * output + state[1];
+            state[1] = b2 * input - a2 * output;
+            *sample = output;
+        }
+        poly_sample
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        //coefficients change every sample while smoothing
+        if self.smoothed_parameters != Some(self.target_parameters())
+            || self.coefficients_for != Some((self.filter_type, sample_timing.sample_rate))
+        {
+            let mut sample_timing = *sample_timing;
+            for poly_sample in buffer {
+                let input = std::mem::replace(poly_sample, poly_sample!());
+                *poly_sample = self.process(&sample_timing, input);
+                sample_timing.tick();
+            }
+            return;
+        }
+
+        let channels = buffer.iter().map(|poly_sample| poly_sample.len()).max().unwrap_or(0);
+        if self.states.len() < channels {
+            self.states.resize(channels, [0.0; 2]);
+        }
+        let [b0, b1, b2, a1, a2] = self.coefficients;
+        for (channel, state) in self.states.iter_mut().enumerate().take(channels) {
+            let [mut state0, mut state1] = *state;
+            for poly_sample in buffer.iter_mut() {
+                if let Some(sample) = poly_sample.get_mut(channel) {
+                    let input = *sample;
+                    let output = b0 * input + state0;
+                    state0 = b1 * input - a1 * output + state1;
+                    state1 = b2 * input - a2 * output;
+                    *sample = output;
+                }
+            }
+            *state = [state0, state1];
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use super::Effect;
+use crate::{PolySample, SampleTiming};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Quantizes the amplitude to a lower bit depth for lo-fi sounds.
+#[derive(Clone)]
+pub struct Bitcrusher {
+    /// Bit depth of the range from `-1.0` to `1.0`, can be fractional for smooth sweeps.
+    pub bits: f32,
+    /// Adds triangular noise of one quantization step before quantizing,
+    /// which turns the distortion of quiet signals into noise.
+    pub dither: bool,
+    rng: StdRng,
+}
+
+impl Bitcrusher {
+    pub fn new(bits: f32) -> Self {
+        Self {
+            bits,
+            dither: false,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+}
+
+impl Default for Bitcrusher {
+    fn default() -> Self {
+        Self::new(8.0)
+    }
+}
+
+impl Effect for Bitcrusher {
+    fn process(
+        &mut self,
+        _sample_timing: &SampleTiming,
+        mut poly_sample: PolySample,
+    ) -> PolySample {
+        let step = 2.0 / self.bits.max(0.0).exp2();
+        for sample in poly_sample.iter_mut() {
+            let dither = if self.dither {
+                (self.rng.gen::<f32>() - self.rng.gen::<f32>()) * step
+            } else {
+                0.0
+            };
+            *sample = ((*sample + dither) / step).round() * step;
+        }
+        poly_sample
+    }
+}
@@ -0,0 +1,110 @@
+use super::{modulation::lfo, DelayLine, Effect};
+use crate::{
+    generator::{Generator, Phase},
+    poly_sample, PolySample, SampleTiming,
+};
+
+/// Thickens the sound by mixing in copies with slowly modulated delays, spread across the stereo field.
+///
+/// Mono input is turned into stereo output.
+pub struct Chorus {
+    /// Number of delayed copies.
+    pub voices: usize,
+    /// LFO frequency in Hz.
+    pub rate: f32,
+    /// Time (in seconds) the delay of the voices swings around `delay`.
+    pub depth: f32,
+    /// Average delay (in seconds) of the voices.
+    pub delay: f32,
+    /// From `0.0` (all voices centered) to `1.0` (voices spread from left to right).
+    pub spread: f32,
+    pub wet: f32,
+    pub dry: f32,
+    /// Replaces the internal LFO, should generate values from `-1.0` to `1.0`.
+    pub modulator: Option<Box<dyn Generator>>,
+    lfo_phase: Phase,
+    line: DelayLine,
+}
+
+impl Chorus {
+    pub fn new(voices: usize, rate: f32, depth: f32, wet: f32) -> Self {
+        Self {
+            voices,
+            rate,
+            depth,
+            delay: 0.015,
+            spread: 1.0,
+            wet,
+            dry: 1.0,
+            modulator: None,
+            lfo_phase: Phase::default(),
+            line: DelayLine::default(),
+        }
+    }
+}
+
+impl Default for Chorus {
+    fn default() -> Self {
+        Self::new(3, 0.8, 0.003, 0.5)
+    }
+}
+
+impl Effect for Chorus {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        self.prepare(sample_timing);
+        self.process_with(sample_timing, poly_sample)
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        self.prepare(sample_timing);
+        let mut sample_timing = *sample_timing;
+        for poly_sample in buffer {
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.process_with(&sample_timing, input);
+            sample_timing.tick();
+        }
+    }
+}
+
+impl Chorus {
+    fn prepare(&mut self, sample_timing: &SampleTiming) {
+        let capacity = ((self.delay + self.depth.abs()) * sample_timing.sample_rate) as usize + 3;
+        self.line.reserve(capacity);
+    }
+
+    fn process_with(
+        &mut self,
+        sample_timing: &SampleTiming,
+        poly_sample: PolySample,
+    ) -> PolySample {
+        let dry_left = poly_sample.first().copied().unwrap_or(0.0);
+        let dry_right = poly_sample.get(1).copied().unwrap_or(dry_left);
+
+        let lfo = lfo(&mut self.lfo_phase, &mut self.modulator, sample_timing, self.rate);
+        let sample_rate = sample_timing.sample_rate;
+
+        let (mut left, mut right) = (0.0, 0.0);
+        for voice in 0..self.voices {
+            let position = voice as f32 / self.voices as f32;
+            let delay = (self.delay + self.depth * lfo(position)) * sample_rate;
+            let sample = self.line.read(delay) / self.voices as f32;
+            //pan from -spread to spread
+            let pan = if self.voices > 1 {
+                (voice as f32 / (self.voices - 1) as f32 * 2.0 - 1.0) * self.spread
+            } else {
+                0.0
+            };
+            left += sample * (1.0 - pan);
+            right += sample * (1.0 + pan);
+        }
+        self.line.write((dry_left + dry_right) / 2.0);
+
+        let output_left = dry_left * self.dry + left * self.wet;
+        let output_right = dry_right * self.dry + right * self.wet;
+        if PolySample::MAX_CHANNELS >= 2 {
+            poly_sample!([output_left, output_right])
+        } else {
+            poly_sample!([(output_left + output_right) / 2.0])
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use super::Effect;
+use crate::{poly_sample, wav::read_wav, PolySample, SampleTiming};
+use anyhow::Result;
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use std::{collections::VecDeque, path::Path, sync::Arc};
+
+#[derive(Clone)]
+struct ChannelState {
+    /// Spectra of the previous input blocks, newest first, one for each partition.
+    input_spectra: VecDeque<Vec<Complex<f32>>>,
+    /// Last two input blocks.
+    input_window: Vec<f32>,
+    output_block: Vec<f32>,
+}
+
+#[derive(Clone)]
+struct ConvolverState {
+    sample_rate: f32,
+    fft: Arc<dyn Fft<f32>>,
+    inverse_fft: Arc<dyn Fft<f32>>,
+    /// Spectra of the impulse response partitions, per channel.
+    partitions: Vec<Vec<Vec<Complex<f32>>>>,
+    channels: Vec<ChannelState>,
+    position: usize,
+    //scratch buffers for the summed spectrum and the convolved block, and for the FFTs
+    output: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+}
+
+/// Convolves the input with an impulse response, e.g. for realistic reverbs or cabinet simulation.
+///
+/// Uses uniformly partitioned FFT convolution, so the output is delayed by [`latency`](Self::latency) samples.
+/// Mono input is convolved with every channel of a multichannel impulse response.
+#[derive(Clone)]
+pub struct Convolver {
+    impulse_response: Arc<Vec<PolySample>>,
+    /// Sample rate of the impulse response.
+    pub sample_rate: f32,
+    block_size: usize,
+    pub wet: f32,
+    /// Volume of the input, which is delayed to stay in time with the convolved signal.
+    pub dry: f32,
+    state: Option<ConvolverState>,
+}
+
+impl Convolver {
+    pub fn new(impulse_response: Vec<PolySample>, sample_rate: f32, block_size: usize) -> Self {
+        Self {
+            impulse_response: Arc::new(impulse_response),
+            sample_rate,
+            block_size: block_size.max(1),
+            wet: 1.0,
+            dry: 0.0,
+            state: None,
+        }
+    }
+
+    /// Loads the impulse response from a PCM or float WAV file.
+    pub fn from_file<P: AsRef<Path>>(path: P, block_size: usize) -> Result<Self> {
+        let (impulse_response, sample_rate) = read_wav(path)?;
+        Ok(Self::new(impulse_response, sample_rate, block_size))
+    }
+
+    /// Delay (in samples) between input and output.
+    pub fn latency(&self) -> usize {
+        self.block_size
+    }
+
+    fn prepare(&self, sample_rate: f32) -> ConvolverState {
+        let block_size = self.block_size;
+        let fft_size = 2 * block_size;
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(fft_size);
+        let inverse_fft = planner.plan_fft_inverse(fft_size);
+
+        //resample linearly to the sample rate of the output
+        let ratio = self.sample_rate / sample_rate;
+        let length = (self.impulse_response.len() as f32 / ratio).ceil() as usize;
+        let channel_count =
+            self.impulse_response.iter().map(|frame| frame.len()).max().unwrap_or(1);
+        let resampled = |channel: usize| {
+            (0..length).map(move |i| {
+                let position = i as f32 * ratio;
+                let index = position as usize;
+                let t = position - index as f32;
+                let sample = |index: usize| {
+                    self.impulse_response.get(index).and_then(|frame| frame.get(channel)).copied()
+                };
+                let current = sample(index).unwrap_or(0.0);
+                current + (sample(index + 1).unwrap_or(current) - current) * t
+            })
+        };
+
+        let partitions = (0..channel_count)
+            .map(|channel| {
+                let samples: Vec<f32> = resampled(channel).collect();
+                samples
+                    .chunks(block_size)
+                    .map(|chunk| {
+                        let mut spectrum: Vec<Complex<f32>> =
+                            chunk.iter().map(|sample| Complex::new(*sample, 0.0)).collect();
+                        spectrum.resize(fft_size, Complex::new(0.0, 0.0));
+                        fft.process(&mut spectrum);
+                        spectrum
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let scratch_len = fft.get_inplace_scratch_len().max(inverse_fft.get_inplace_scratch_len());
+        ConvolverState {
+            sample_rate,
+            fft,
+            inverse_fft,
+            partitions,
+            channels: Vec::new(),
+            position: 0,
+            output: vec![Complex::new(0.0, 0.0); fft_size],
+            scratch: vec![Complex::new(0.0, 0.0); scratch_len],
+        }
+    }
+}
+
+impl ConvolverState {
+    fn process_block(&mut self, block_size: usize) {
+        let fft_size = 2 * block_size;
+        for (channel, state) in self.channels.iter_mut().enumerate() {
+            let partitions = &self.partitions[channel.min(self.partitions.len() - 1)];
+
+            //the spectrum of the oldest block is reused for the newest
+            if let Some(mut spectrum) = state.input_spectra.pop_back() {
+                for (bin, sample) in spectrum.iter_mut().zip(&state.input_window) {
+                    *bin = Complex::new(*sample, 0.0);
+                }
+                self.fft.process_with_scratch(&mut spectrum, &mut self.scratch);
+                state.input_spectra.push_front(spectrum);
+            }
+
+            let output = &mut self.output;
+            for bin in output.iter_mut() {
+                *bin = Complex::new(0.0, 0.0);
+            }
+            for (input_spectrum, partition) in state.input_spectra.iter().zip(partitions) {
+                for ((output, input), partition) in
+                    output.iter_mut().zip(input_spectrum).zip(partition)
+                {
+                    *output += input * partition;
+                }
+            }
+            self.inverse_fft.process_with_scratch(output, &mut self.scratch);
+
+            //overlap-save: only the second half is free of circular aliasing
+            for (output_sample, sample) in state.output_block.iter_mut().zip(&output[block_size..])
+            {
+                *output_sample = sample.re / fft_size as f32;
+            }
+            state.input_window.copy_within(block_size.., 0);
+        }
+    }
+}
+
+impl Effect for Convolver {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        let block_size = self.block_size;
+        if self.state.as_ref().map(|state| state.sample_rate) != Some(sample_timing.sample_rate) {
+            self.state = Some(self.prepare(sample_timing.sample_rate));
+        }
+        let state = self.state.as_mut().unwrap();
+        if state.partitions.is_empty() {
+            return poly_sample * self.dry;
+        }
+
+        let channel_count =
+            poly_sample.len().max(state.partitions.len()).min(PolySample::MAX_CHANNELS);
+        while state.channels.len() < channel_count {
+            let partition_count = state.partitions[0].len();
+            state.channels.push(ChannelState {
+                input_spectra: vec![vec![Complex::new(0.0, 0.0); 2 * block_size]; partition_count]
+                    .into(),
+                input_window: vec![0.0; 2 * block_size],
+                output_block: vec![0.0; block_size],
+            });
+        }
+
+        let position = state.position;
+        let mut output = poly_sample!();
+        for (channel, channel_state) in state.channels.iter_mut().enumerate() {
+            let input = poly_sample.get(channel).or_else(|| poly_sample.first()).copied();
+            let input = input.unwrap_or(0.0);
+            let delayed_input = channel_state.input_window[position];
+            channel_state.input_window[block_size + position] = input;
+            output.push(channel_state.output_block[position] * self.wet + delayed_input * self.dry);
+        }
+
+        state.position += 1;
+        if state.position == block_size {
+            state.position = 0;
+            state.process_block(block_size);
+        }
+        output
+    }
+}
@@ -1,12 +1,32 @@
-use super::Effect;
-use crate::{PolySample, SampleTiming};
-use std::collections::VecDeque;
+use super::{Biquad, DelayLine, Effect};
+use crate::{poly_sample, PolySample, SampleTiming};
+use std::f32::consts::FRAC_1_SQRT_2;
 
-#[derive(Clone, Default)]
+/// Echo effect with interpolated delay time, so `delay` can be modulated smoothly.
+///
+/// The feedback path can be filtered and saturated like on an analog delay.
+#[derive(Clone)]
 pub struct Delay {
+    /// Time (in seconds) between the echoes, changes glide in pitch like on a tape delay.
     pub delay: f32,
+    /// Volume of each echo relative to the previous one, the first echo is `feedback` times the input.
     pub feedback: f32,
-    buffer: VecDeque<PolySample>,
+    /// Alternates the echoes between the left and right channel.
+    pub ping_pong: bool,
+    /// Cutoff (in Hz) of the high-pass filter in the feedback path.
+    pub low_cut: Option<f32>,
+    /// Cutoff (in Hz) of the low-pass filter in the feedback path.
+    pub high_cut: Option<f32>,
+    /// From `0.0` (clean) to `1.0`, soft clips the feedback path.
+    pub saturation: f32,
+    pub wet: f32,
+    pub dry: f32,
+    /// Time (in seconds) it takes changes of `delay` to mostly take effect.
+    pub smoothing: f32,
+    smoothed_delay: Option<f32>,
+    lines: Vec<DelayLine>,
+    low_cut_filter: Biquad,
+    high_cut_filter: Biquad,
 }
 
 impl Delay {
@@ -14,18 +34,129 @@ impl Delay {
         Self {
             delay,
             feedback,
-            buffer: VecDeque::new(),
+            ping_pong: false,
+            low_cut: None,
+            high_cut: None,
+            saturation: 0.0,
+            wet: 1.0,
+            dry: 1.0,
+            smoothing: 0.05,
+            smoothed_delay: None,
+            lines: Vec::new(),
+            low_cut_filter: Biquad::high_pass(20.0, FRAC_1_SQRT_2),
+            high_cut_filter: Biquad::low_pass(20000.0, FRAC_1_SQRT_2),
         }
     }
+
+    /// Delay of `note_value` at `bpm`, see [`sync_to_tempo`](Self::sync_to_tempo).
+    pub fn tempo_synced(bpm: f32, note_value: f32, feedback: f32) -> Self {
+        let mut delay = Self::new(0.0, feedback);
+        delay.sync_to_tempo(bpm, note_value);
+        delay
+    }
+
+    /// Sets `delay` to the duration of `note_value` at `bpm`,
+    /// e.g. `0.25` for a quarter note or `3.0 / 16.0` for a dotted eighth note.
+    pub fn sync_to_tempo(&mut self, bpm: f32, note_value: f32) {
+        self.delay = 60.0 / bpm * 4.0 * note_value;
+    }
+
+    /// Clears all echoes.
+    pub fn reset(&mut self) {
+        self.lines.clear();
+        self.smoothed_delay = None;
+        self.low_cut_filter.reset();
+        self.high_cut_filter.reset();
+    }
+}
+
+impl Default for Delay {
+    fn default() -> Self {
+        Self::new(0.0, 0.0)
+    }
 }
 
 impl Effect for Delay {
-    fn process(&mut self, sample_timing: &SampleTiming, mut poly_sample: PolySample) -> PolySample {
-        let buffer_size = sample_timing.duration_to_sample_count(self.delay);
-        if self.buffer.len() == buffer_size {
-            poly_sample += self.buffer.pop_front().unwrap();
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        let smoothing = self.smoothing(sample_timing.sample_rate);
+        self.echo(sample_timing, poly_sample, smoothing)
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let smoothing = self.smoothing(sample_timing.sample_rate);
+        let mut sample_timing = *sample_timing;
+        for poly_sample in buffer {
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.echo(&sample_timing, input, smoothing);
+            sample_timing.tick();
         }
-        self.buffer.push_back(poly_sample.clone() * self.feedback);
-        poly_sample
+    }
+}
+
+impl Delay {
+    //target delay in samples and the smoothing factor, if smoothing
+    fn smoothing(&self, sample_rate: f32) -> (f32, Option<f32>) {
+        let target = (self.delay * sample_rate).max(1.0);
+        let factor = if self.smoothing > 0.0 {
+            Some(1.0 - (-1.0 / (self.smoothing * sample_rate)).exp())
+        } else {
+            None
+        };
+        (target, factor)
+    }
+
+    fn echo(
+        &mut self,
+        sample_timing: &SampleTiming,
+        poly_sample: PolySample,
+        (target, factor): (f32, Option<f32>),
+    ) -> PolySample {
+        let delay = match (self.smoothed_delay, factor) {
+            (Some(delay), Some(factor)) => delay + (target - delay) * factor,
+            _ => target,
+        };
+        self.smoothed_delay = Some(delay);
+
+        let ping_pong = self.ping_pong && PolySample::MAX_CHANNELS >= 2;
+        let channel_count = if ping_pong { poly_sample.len().max(2) } else { poly_sample.len() };
+        let capacity = delay.ceil() as usize + 3;
+        self.lines.resize_with(channel_count, || DelayLine::new(capacity));
+        for line in &mut self.lines {
+            line.reserve(capacity);
+        }
+
+        //mono input is spread over all channels
+        let input = |channel: usize| {
+            poly_sample.get(channel).or_else(|| poly_sample.first()).copied().unwrap_or(0.0)
+        };
+        let delayed = PolySample(self.lines.iter().map(|line| line.read(delay)).collect());
+
+        let mut feedback = PolySample(
+            (0..channel_count)
+                .map(|channel| match (ping_pong, channel) {
+                    (true, 0) => (input(0) + input(1)) / 2.0 + delayed[1],
+                    (true, 1) => delayed[0],
+                    _ => input(channel) + delayed[channel],
+                })
+                .map(|sample| sample * self.feedback)
+                .collect(),
+        );
+        if let Some(low_cut) = self.low_cut {
+            self.low_cut_filter.cutoff = low_cut;
+            feedback = self.low_cut_filter.process(sample_timing, feedback);
+        }
+        if let Some(high_cut) = self.high_cut {
+            self.high_cut_filter.cutoff = high_cut;
+            feedback = self.high_cut_filter.process(sample_timing, feedback);
+        }
+        for (line, sample) in self.lines.iter_mut().zip(feedback.iter()) {
+            line.write(sample + (sample.tanh() - sample) * self.saturation);
+        }
+
+        PolySample(
+            (0..channel_count)
+                .map(|channel| input(channel) * self.dry + delayed[channel] * self.wet)
+                .collect(),
+        )
     }
 }
@@ -0,0 +1,65 @@
+/// Single channel ring buffer that can be read at fractional delays, the building block of delay-based effects.
+#[derive(Clone)]
+pub struct DelayLine {
+    buffer: Vec<f32>,
+    write_index: usize,
+}
+
+impl DelayLine {
+    /// Creates a silent delay line holding `capacity` samples.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: vec![0.0; capacity.max(1)],
+            write_index: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Grows the delay line to hold at least `capacity` samples, keeping its content.
+    pub fn reserve(&mut self, capacity: usize) {
+        if capacity > self.buffer.len() {
+            let additional = capacity - self.buffer.len();
+            //the new samples are the oldest ones
+            self.buffer.splice(self.write_index..self.write_index, vec![0.0; additional]);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.iter_mut().for_each(|sample| *sample = 0.0);
+    }
+
+    pub fn write(&mut self, sample: f32) {
+        self.buffer[self.write_index] = sample;
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+    }
+
+    /// Reads the sample written `delay` samples ago, with cubic hermite interpolation between samples.
+    ///
+    /// `delay` is clamped from `1.0` to [`capacity`](Self::capacity).
+    pub fn read(&self, delay: f32) -> f32 {
+        let length = self.buffer.len();
+        let delay = delay.max(1.0).min(length as f32);
+        let index = delay.floor() as usize;
+        let t = delay - index as f32;
+        let sample = |age: usize| {
+            let age = age.max(1).min(length);
+            self.buffer[(self.write_index + length - age) % length]
+        };
+
+        let (y0, y1, y2, y3) =
+            (sample(index - 1), sample(index), sample(index + 1), sample(index + 2));
+        let c1 = 0.5 * (y2 - y0);
+        let c2 = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+        let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+        ((c3 * t + c2) * t + c1) * t + y1
+    }
+}
+
+impl Default for DelayLine {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
@@ -0,0 +1,162 @@
+use super::{dynamics::from_db, Biquad, Effect};
+use crate::{poly_sample, PolySample, SampleTiming};
+use std::sync::Arc;
+
+//cutoff of the anti-aliasing filters relative to the sample rate, and the Q of the butterworth stages
+const ANTI_ALIASING_CUTOFF: f32 = 0.45;
+const BUTTERWORTH_QS: [f32; 4] = [0.509_795_6, 0.601_344_9, 0.899_976_2, 2.562_915_5];
+const TUBE_BIAS: f32 = 0.3;
+
+/// Shape of a [`Distortion`], mapping input to output samples.
+#[derive(Clone)]
+pub enum TransferCurve {
+    /// Smooth `tanh` saturation.
+    SoftClip,
+    /// Cuts everything outside `-1.0..1.0`.
+    HardClip,
+    /// Mirrors everything outside `-1.0..1.0` back into the range.
+    Foldback,
+    /// Asymmetric saturation adding even harmonics, which also adds a DC offset.
+    Tube,
+    Custom(Arc<dyn Fn(f32) -> f32 + Send + Sync>),
+    /// Output values for inputs evenly spread from `-1.0` to `1.0`, linearly interpolated.
+    Table(Arc<Vec<f32>>),
+}
+
+impl TransferCurve {
+    pub fn custom<F: 'static + Fn(f32) -> f32 + Send + Sync>(f: F) -> Self {
+        Self::Custom(Arc::new(f))
+    }
+
+    pub fn table(table: Vec<f32>) -> Self {
+        Self::Table(Arc::new(table))
+    }
+
+    pub fn shape(&self, x: f32) -> f32 {
+        match self {
+            Self::SoftClip => x.tanh(),
+            Self::HardClip => x.clamp(-1.0, 1.0),
+            Self::Foldback => 1.0 - ((x + 1.0).rem_euclid(4.0) - 2.0).abs(),
+            Self::Tube => (x + TUBE_BIAS).tanh() - TUBE_BIAS.tanh(),
+            Self::Custom(f) => f(x),
+            Self::Table(table) => match table.len() {
+                0 => x,
+                1 => table[0],
+                length => {
+                    let position = (x.clamp(-1.0, 1.0) + 1.0) / 2.0 * (length - 1) as f32;
+                    let index = (position as usize).min(length - 2);
+                    let t = position - index as f32;
+                    table[index] + (table[index + 1] - table[index]) * t
+                }
+            },
+        }
+    }
+}
+
+/// Waveshaping distortion, oversampled to reduce aliasing.
+#[derive(Clone)]
+pub struct Distortion {
+    pub curve: TransferCurve,
+    /// Gain in dB before the curve, more gain distorts more.
+    pub input_gain: f32,
+    /// Gain in dB after the curve.
+    pub output_gain: f32,
+    /// From `0.0` (only input) to `1.0` (only distorted).
+    pub mix: f32,
+    /// Factor the curve is applied at a higher sample rate, e.g. `2`, `4` or `8`. `1` disables oversampling.
+    pub oversampling: usize,
+    upsampling_filters: [Biquad; 4],
+    downsampling_filters: [Biquad; 4],
+}
+
+impl Distortion {
+    pub fn new(curve: TransferCurve, input_gain: f32) -> Self {
+        let anti_aliasing_filters = || {
+            BUTTERWORTH_QS.map(|q| {
+                let mut filter = Biquad::low_pass(20000.0, q);
+                filter.smoothing = 0.0;
+                filter
+            })
+        };
+        Self {
+            curve,
+            input_gain,
+            output_gain: 0.0,
+            mix: 1.0,
+            oversampling: 4,
+            upsampling_filters: anti_aliasing_filters(),
+            downsampling_filters: anti_aliasing_filters(),
+        }
+    }
+}
+
+impl Default for Distortion {
+    fn default() -> Self {
+        Self::new(TransferCurve::SoftClip, 12.0)
+    }
+}
+
+impl Effect for Distortion {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        let gains = self.gains();
+        self.distort(sample_timing, poly_sample, gains)
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let gains = self.gains();
+        for poly_sample in buffer {
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.distort(sample_timing, input, gains);
+        }
+    }
+}
+
+impl Distortion {
+    //linear gains before the curve and of the distorted output
+    fn gains(&self) -> (f32, f32) {
+        (from_db(self.input_gain), from_db(self.output_gain) * self.mix)
+    }
+
+    fn distort(
+        &mut self,
+        sample_timing: &SampleTiming,
+        poly_sample: PolySample,
+        (input_gain, output_gain): (f32, f32),
+    ) -> PolySample {
+        let dry = poly_sample.clone() * (1.0 - self.mix);
+        let driven = poly_sample * input_gain;
+        let shape = |mut poly_sample: PolySample, curve: &TransferCurve| {
+            for sample in poly_sample.iter_mut() {
+                *sample = curve.shape(*sample);
+            }
+            poly_sample
+        };
+
+        let wet = if self.oversampling <= 1 {
+            shape(driven, &self.curve)
+        } else {
+            let factor = self.oversampling;
+            let oversampled_timing = SampleTiming::new(sample_timing.sample_rate * factor as f32);
+            let cutoff = sample_timing.sample_rate * ANTI_ALIASING_CUTOFF;
+            let filter = |filters: &mut [Biquad; 4], mut poly_sample: PolySample| {
+                for filter in filters {
+                    filter.cutoff = cutoff;
+                    poly_sample = filter.process(&oversampled_timing, poly_sample);
+                }
+                poly_sample
+            };
+
+            //zero stuffing, the filters interpolate the samples in between
+            let silence = driven.clone() * 0.0;
+            let mut wet = silence.clone();
+            for i in 0..factor {
+                let upsampled =
+                    if i == 0 { driven.clone() * factor as f32 } else { silence.clone() };
+                let upsampled = filter(&mut self.upsampling_filters, upsampled);
+                wet = filter(&mut self.downsampling_filters, shape(upsampled, &self.curve));
+            }
+            wet
+        };
+        wet * output_gain + dry
+    }
+}
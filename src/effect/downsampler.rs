@@ -0,0 +1,48 @@
+use super::Effect;
+use crate::{PolySample, SampleTiming};
+
+/// Holds samples to emulate a lower sample rate, aliasing included.
+#[derive(Clone)]
+pub struct Downsampler {
+    /// Emulated sample rate in Hz, can be changed every sample for smooth sweeps.
+    pub sample_rate: f32,
+    held_sample: Option<PolySample>,
+    phase: f32,
+}
+
+impl Downsampler {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            held_sample: None,
+            phase: 0.0,
+        }
+    }
+}
+
+impl Default for Downsampler {
+    fn default() -> Self {
+        Self::new(8000.0)
+    }
+}
+
+impl Effect for Downsampler {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        if self.sample_rate >= sample_timing.sample_rate {
+            self.held_sample = None;
+            return poly_sample;
+        }
+        match self.held_sample {
+            Some(ref held_sample) if self.phase < 1.0 => {
+                self.phase += self.sample_rate.max(0.0) / sample_timing.sample_rate;
+                held_sample.clone()
+            }
+            _ => {
+                self.phase =
+                    self.phase.fract() + self.sample_rate.max(0.0) / sample_timing.sample_rate;
+                self.held_sample = Some(poly_sample.clone());
+                poly_sample
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use super::{
+    dynamics::{Dynamics, Factors},
+    Detection, Effect, SidechainEffect,
+};
+use crate::{PolySample, SampleTiming};
+
+/// Lowers the volume while the key is above `threshold`, e.g. for bass pumping along with the kick drum.
+///
+/// Meant to be used with [`process_sidechain`](SidechainEffect::process_sidechain),
+/// as [`Effect`] it is keyed by its own input.
+#[derive(Clone)]
+pub struct Ducker {
+    /// Level in dB of the key above which the volume is lowered.
+    pub threshold: f32,
+    /// Gain reduction in dB while ducking.
+    pub depth: f32,
+    /// Time (in seconds) it takes the volume to mostly drop.
+    pub attack: f32,
+    /// Time (in seconds) the volume stays low after the key fell below the threshold.
+    pub hold: f32,
+    /// Time (in seconds) it takes the volume to mostly recover.
+    pub release: f32,
+    pub detection: Detection,
+    hold_counter: usize,
+    dynamics: Dynamics,
+}
+
+impl Ducker {
+    pub fn new(threshold: f32, depth: f32, attack: f32, release: f32) -> Self {
+        Self {
+            threshold,
+            depth,
+            attack,
+            hold: 0.0,
+            release,
+            detection: Detection::Peak,
+            hold_counter: usize::MAX,
+            dynamics: Dynamics::default(),
+        }
+    }
+
+    /// Current gain reduction in dB, e.g. for metering.
+    pub fn gain_reduction(&self) -> f32 {
+        self.dynamics.gain_reduction()
+    }
+}
+
+impl Default for Ducker {
+    fn default() -> Self {
+        Self::new(-30.0, 12.0, 0.005, 0.2)
+    }
+}
+
+impl Effect for Ducker {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        let key = poly_sample.clone();
+        self.process_sidechain(sample_timing, poly_sample, key)
+    }
+}
+
+impl SidechainEffect for Ducker {
+    fn process_sidechain(
+        &mut self,
+        sample_timing: &SampleTiming,
+        poly_sample: PolySample,
+        key: PolySample,
+    ) -> PolySample {
+        let factors = Factors::new(sample_timing.sample_rate, self.attack, self.release);
+        let mut targets = self.dynamics.levels(&factors, &key, self.detection, true);
+        let hold_size = sample_timing.duration_to_sample_count(self.hold);
+        if targets[0] >= self.threshold {
+            self.hold_counter = 0;
+        } else {
+            self.hold_counter = self.hold_counter.saturating_add(1);
+        }
+        targets[0] = if self.hold_counter <= hold_size { -self.depth } else { 0.0 };
+        self.dynamics.smooth(&factors, &targets, false);
+        self.dynamics.apply(poly_sample, 0.0)
+    }
+}
@@ -0,0 +1,119 @@
+use crate::PolySample;
+
+//time constant of the RMS detection in seconds
+const RMS_TIME: f32 = 0.01;
+
+/// How the level of the input is measured by dynamics effects.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Detection {
+    /// Reacts to every peak, best for catching transients.
+    Peak,
+    /// Follows the average power, closer to the perceived loudness.
+    Rms,
+}
+
+pub(super) fn to_db(amplitude: f32) -> f32 {
+    20.0 * amplitude.abs().max(1e-9).log10()
+}
+
+pub(crate) fn from_db(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+/// Factor of a one-pole smoother that mostly reaches its target after `time` seconds.
+pub(super) fn smoothing_factor(time: f32, sample_rate: f32) -> f32 {
+    if time > 0.0 {
+        1.0 - (-1.0 / (time * sample_rate)).exp()
+    } else {
+        1.0
+    }
+}
+
+/// Smoothing factors of [`Dynamics`] at a sample rate, so blocks compute them only once.
+#[derive(Clone, Copy)]
+pub(super) struct Factors {
+    rms: f32,
+    attack: f32,
+    release: f32,
+}
+
+impl Factors {
+    pub fn new(sample_rate: f32, attack: f32, release: f32) -> Self {
+        Self {
+            rms: smoothing_factor(RMS_TIME, sample_rate),
+            attack: smoothing_factor(attack, sample_rate),
+            release: smoothing_factor(release, sample_rate),
+        }
+    }
+}
+
+/// Level detection and gain smoothing shared by the dynamics effects.
+///
+/// Works on one detector for all channels if stereo linked, otherwise on one detector per channel.
+#[derive(Clone, Default)]
+pub(super) struct Dynamics {
+    mean_squares: Vec<f32>,
+    gains: Vec<f32>,
+}
+
+impl Dynamics {
+    /// Detected level (in dB) of every detector.
+    pub fn levels(
+        &mut self,
+        factors: &Factors,
+        poly_sample: &PolySample,
+        detection: Detection,
+        stereo_link: bool,
+    ) -> PolySample {
+        let detector_count = if stereo_link { 1 } else { poly_sample.len() };
+        self.mean_squares.resize(detector_count, 0.0);
+        let channel_count = poly_sample.len().max(1) as f32;
+
+        PolySample(
+            self.mean_squares
+                .iter_mut()
+                .enumerate()
+                .map(|(detector, mean_square)| {
+                    let (peak, square) = if stereo_link {
+                        let peak = poly_sample.iter().fold(0.0, |peak: f32, s| peak.max(s.abs()));
+                        let square = poly_sample.iter().map(|s| s * s).sum::<f32>() / channel_count;
+                        (peak, square)
+                    } else {
+                        let sample = poly_sample[detector];
+                        (sample.abs(), sample * sample)
+                    };
+                    *mean_square += (square - *mean_square) * factors.rms;
+                    match detection {
+                        Detection::Peak => to_db(peak),
+                        Detection::Rms => to_db(mean_square.sqrt()),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Moves the gains (in dB) towards `targets`, with the attack while reducing the gain
+    /// or, if `attack_opens`, while increasing it, and with the release otherwise.
+    pub fn smooth(&mut self, factors: &Factors, targets: &PolySample, attack_opens: bool) {
+        self.gains.resize(targets.len(), 0.0);
+        for (gain, target) in self.gains.iter_mut().zip(targets.iter()) {
+            let factor =
+                if (*target < *gain) != attack_opens { factors.attack } else { factors.release };
+            *gain += (target - *gain) * factor;
+        }
+    }
+
+    /// Applies the smoothed gains and `makeup_gain` (in dB).
+    pub fn apply(&self, mut poly_sample: PolySample, makeup_gain: f32) -> PolySample {
+        for (channel, sample) in poly_sample.iter_mut().enumerate() {
+            let gain = self.gains.get(channel).or_else(|| self.gains.first()).copied();
+            *sample *= from_db(gain.unwrap_or(0.0) + makeup_gain);
+        }
+        poly_sample
+    }
+
+    /// Current gain reduction in dB of the detector reducing the most.
+    pub fn gain_reduction(&self) -> f32 {
+        self.gains.iter().fold(0.0, |reduction: f32, gain| reduction.max(-gain))
+    }
+}
@@ -0,0 +1,113 @@
+use super::{
+    dynamics::{Dynamics, Factors},
+    Detection, Effect, SidechainEffect,
+};
+use crate::{poly_sample, PolySample, SampleTiming};
+
+/// Reduces the level of signals below `threshold` by `ratio`, e.g. to suppress background noise.
+#[derive(Clone)]
+pub struct Expander {
+    /// Level in dB below which the gain is reduced.
+    pub threshold: f32,
+    /// Output level change in dB for every dB of input level change below the threshold.
+    pub ratio: f32,
+    /// Width in dB of the soft transition around the threshold.
+    pub knee: f32,
+    /// Maximum gain reduction in dB.
+    pub range: f32,
+    /// Time (in seconds) it takes the gain to mostly recover when the level rises above the threshold.
+    pub attack: f32,
+    /// Time (in seconds) it takes the gain reduction to mostly take effect.
+    pub release: f32,
+    pub makeup_gain: f32,
+    pub detection: Detection,
+    /// Detects the level of all channels together, so the stereo image doesn't shift.
+    pub stereo_link: bool,
+    dynamics: Dynamics,
+}
+
+impl Expander {
+    pub fn new(threshold: f32, ratio: f32, attack: f32, release: f32) -> Self {
+        Self {
+            threshold,
+            ratio,
+            knee: 6.0,
+            range: 40.0,
+            attack,
+            release,
+            makeup_gain: 0.0,
+            detection: Detection::Rms,
+            stereo_link: true,
+            dynamics: Dynamics::default(),
+        }
+    }
+
+    /// Current gain reduction in dB, e.g. for metering.
+    pub fn gain_reduction(&self) -> f32 {
+        self.dynamics.gain_reduction()
+    }
+
+    /// Static gain (in dB) for a `level` in dB, without attack and release.
+    pub fn gain(&self, level: f32) -> f32 {
+        let over = level - self.threshold;
+        let slope = self.ratio.max(1.0) - 1.0;
+        let gain = if 2.0 * over >= self.knee {
+            0.0
+        } else if 2.0 * over > -self.knee {
+            -slope * (over - self.knee / 2.0).powi(2) / (2.0 * self.knee)
+        } else {
+            slope * over
+        };
+        gain.max(-self.range)
+    }
+}
+
+impl Expander {
+    fn process_with(
+        &mut self,
+        factors: &Factors,
+        poly_sample: PolySample,
+        key: PolySample,
+    ) -> PolySample {
+        let mut targets = self.dynamics.levels(factors, &key, self.detection, self.stereo_link);
+        for level in targets.iter_mut() {
+            *level = self.gain(*level);
+        }
+        self.dynamics.smooth(factors, &targets, true);
+        self.dynamics.apply(poly_sample, self.makeup_gain)
+    }
+}
+
+impl Default for Expander {
+    fn default() -> Self {
+        Self::new(-40.0, 2.0, 0.001, 0.1)
+    }
+}
+
+impl Effect for Expander {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        let key = poly_sample.clone();
+        self.process_sidechain(sample_timing, poly_sample, key)
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let factors = Factors::new(sample_timing.sample_rate, self.attack, self.release);
+        for poly_sample in buffer {
+            let key = poly_sample.clone();
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.process_with(&factors, input, key);
+        }
+    }
+}
+
+impl SidechainEffect for Expander {
+    fn process_sidechain(
+        &mut self,
+        sample_timing: &SampleTiming,
+        poly_sample: PolySample,
+        key: PolySample,
+    ) -> PolySample {
+        let factors = Factors::new(sample_timing.sample_rate, self.attack, self.release);
+        self.process_with(&factors, poly_sample, key)
+    }
+}
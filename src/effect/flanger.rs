@@ -0,0 +1,95 @@
+use super::{modulation::lfo, DelayLine, Effect};
+use crate::{
+    generator::{Generator, Phase},
+    poly_sample, PolySample, SampleTiming,
+};
+
+/// Sweeping comb filter made of a short modulated delay with feedback.
+///
+/// The LFO of the second channel is a quarter period ahead for a wider stereo image.
+pub struct Flanger {
+    /// LFO frequency in Hz.
+    pub rate: f32,
+    /// Time (in seconds) the delay sweeps above `delay`.
+    pub depth: f32,
+    /// Minimum delay in seconds.
+    pub delay: f32,
+    /// From `-1.0` to `1.0`, negative values give a hollower sound.
+    pub feedback: f32,
+    pub wet: f32,
+    pub dry: f32,
+    /// Replaces the internal LFO, should generate values from `-1.0` to `1.0`.
+    pub modulator: Option<Box<dyn Generator>>,
+    lfo_phase: Phase,
+    lines: Vec<DelayLine>,
+}
+
+impl Flanger {
+    pub fn new(rate: f32, depth: f32, feedback: f32) -> Self {
+        Self {
+            rate,
+            depth,
+            delay: 0.001,
+            feedback,
+            wet: 1.0,
+            dry: 1.0,
+            modulator: None,
+            lfo_phase: Phase::default(),
+            lines: Vec::new(),
+        }
+    }
+}
+
+impl Default for Flanger {
+    fn default() -> Self {
+        Self::new(0.25, 0.003, 0.5)
+    }
+}
+
+impl Effect for Flanger {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        self.prepare(sample_timing, poly_sample.len());
+        self.process_with(sample_timing, poly_sample)
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let channels = buffer.iter().map(|poly_sample| poly_sample.len()).max().unwrap_or(0);
+        self.prepare(sample_timing, channels);
+        let mut sample_timing = *sample_timing;
+        for poly_sample in buffer {
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.process_with(&sample_timing, input);
+            sample_timing.tick();
+        }
+    }
+}
+
+impl Flanger {
+    //adds and grows the delay lines of `channels` channels
+    fn prepare(&mut self, sample_timing: &SampleTiming, channels: usize) {
+        let capacity = ((self.delay + self.depth.abs()) * sample_timing.sample_rate) as usize + 3;
+        if self.lines.len() < channels {
+            self.lines.resize_with(channels, DelayLine::default);
+        }
+        for line in self.lines.iter_mut().take(channels) {
+            line.reserve(capacity);
+        }
+    }
+
+    fn process_with(
+        &mut self,
+        sample_timing: &SampleTiming,
+        mut poly_sample: PolySample,
+    ) -> PolySample {
+        let lfo = lfo(&mut self.lfo_phase, &mut self.modulator, sample_timing, self.rate);
+        let sample_rate = sample_timing.sample_rate;
+        for (channel, (sample, line)) in poly_sample.iter_mut().zip(&mut self.lines).enumerate() {
+            let offset = if channel % 2 == 1 { 0.25 } else { 0.0 };
+            let delay = (self.delay + self.depth * (lfo(offset) + 1.0) / 2.0) * sample_rate;
+            let delayed = line.read(delay);
+            line.write(*sample + delayed * self.feedback);
+            *sample = *sample * self.dry + delayed * self.wet;
+        }
+        poly_sample
+    }
+}
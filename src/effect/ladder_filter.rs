@@ -0,0 +1,91 @@
+use super::Effect;
+use crate::{PolySample, SampleTiming};
+use std::f32::consts::PI;
+
+/// Moog-style 4-pole low-pass filter, with saturation in its feedback loop.
+///
+/// Built from zero-delay-feedback stages, so it stays stable when `cutoff` is modulated every sample.
+#[derive(Clone)]
+pub struct LadderFilter {
+    /// Cutoff frequency in Hz.
+    pub cutoff: f32,
+    /// Amount of feedback, where the filter starts to self-oscillate at `1.0`.
+    pub resonance: f32,
+    /// Gain before the saturation, higher values distort more.
+    pub drive: f32,
+    states: Vec<[f32; 4]>,
+}
+
+impl LadderFilter {
+    pub fn new(cutoff: f32, resonance: f32, drive: f32) -> Self {
+        Self {
+            cutoff,
+            resonance,
+            drive,
+            states: Vec::new(),
+        }
+    }
+}
+
+impl LadderFilter {
+    fn coefficients(&self, sample_rate: f32) -> (f32, f32) {
+        let cutoff = self.cutoff.max(1.0).min(sample_rate * 0.49);
+        let g = (PI * cutoff / sample_rate).tan();
+        (g, 4.0 * self.resonance)
+    }
+
+    #[inline]
+    fn process_stages(drive: f32, g: f32, k: f32, state: &mut [f32; 4], sample: f32) -> f32 {
+        let stage_gain = g / (1.0 + g);
+        //resolve the feedback loop: output = stage_gain^4 * input + contribution of the states
+        let state_output = state.iter().fold(0.0, |output, s| output * stage_gain + s / (1.0 + g));
+        let linear_output =
+            (stage_gain.powi(4) * drive * sample + state_output) / (1.0 + k * stage_gain.powi(4));
+        let mut stage_input = (drive * sample - k * linear_output).tanh();
+
+        for s in state.iter_mut() {
+            let v = (stage_input - *s) * stage_gain;
+            let stage_output = v + *s;
+            *s = stage_output + v;
+            stage_input = stage_output;
+        }
+        stage_input
+    }
+}
+
+impl Default for LadderFilter {
+    fn default() -> Self {
+        Self::new(1000.0, 0.0, 1.0)
+    }
+}
+
+impl Effect for LadderFilter {
+    fn process(&mut self, sample_timing: &SampleTiming, mut poly_sample: PolySample) -> PolySample {
+        if self.states.len() < poly_sample.len() {
+            self.states.resize(poly_sample.len(), [0.0; 4]);
+        }
+
+        let (g, k) = self.coefficients(sample_timing.sample_rate);
+        for (sample, state) in poly_sample.iter_mut().zip(self.states.iter_mut()) {
+            *sample = Self::process_stages(self.drive, g, k, state, *sample);
+        }
+        poly_sample
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let channels = buffer.iter().map(|poly_sample| poly_sample.len()).max().unwrap_or(0);
+        if self.states.len() < channels {
+            self.states.resize(channels, [0.0; 4]);
+        }
+        let (g, k) = self.coefficients(sample_timing.sample_rate);
+        for channel in 0..channels {
+            let mut state = self.states[channel];
+            for poly_sample in buffer.iter_mut() {
+                if let Some(sample) = poly_sample.get_mut(channel) {
+                    *sample = Self::process_stages(self.drive, g, k, &mut state, *sample);
+                }
+            }
+            self.states[channel] = state;
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use super::{
+    dynamics::{from_db, smoothing_factor, to_db},
+    Effect,
+};
+use crate::{poly_sample, PolySample, SampleTiming};
+use std::collections::VecDeque;
+
+/// Brickwall limiter, the output never exceeds `ceiling`.
+///
+/// The input is delayed by `lookahead`, so the gain can be reduced smoothly before a peak arrives.
+#[derive(Clone)]
+pub struct Limiter {
+    /// Maximum output level in dB.
+    pub ceiling: f32,
+    /// Time (in seconds) the gain reduction starts before a peak. Changes reset the limiter.
+    pub lookahead: f32,
+    /// Time (in seconds) it takes the gain to mostly recover.
+    pub release: f32,
+    /// Detects the level of all channels together, so the stereo image doesn't shift.
+    pub stereo_link: bool,
+    lookahead_size: usize,
+    delay_buffer: VecDeque<PolySample>,
+    //required gains within the lookahead, with the minimum at the front
+    minimum_gains: Vec<VecDeque<(usize, f32)>>,
+    //minimum gains within the lookahead and their sum
+    averaged_gains: Vec<(VecDeque<f32>, f32)>,
+    gains: Vec<f32>,
+    clock: usize,
+}
+
+impl Limiter {
+    pub fn new(ceiling: f32, lookahead: f32, release: f32) -> Self {
+        Self {
+            ceiling,
+            lookahead,
+            release,
+            stereo_link: true,
+            lookahead_size: 0,
+            delay_buffer: VecDeque::new(),
+            minimum_gains: Vec::new(),
+            averaged_gains: Vec::new(),
+            gains: Vec::new(),
+            clock: 0,
+        }
+    }
+
+    /// Current gain reduction in dB, e.g. for metering.
+    pub fn gain_reduction(&self) -> f32 {
+        -to_db(self.gains.iter().fold(1.0, |minimum: f32, gain| minimum.min(*gain)))
+    }
+
+    /// Latency (in samples) caused by the lookahead at `sample_rate`.
+    pub fn latency(&self, sample_rate: f32) -> usize {
+        (self.lookahead * sample_rate) as usize
+    }
+
+    fn reset(&mut self, lookahead_size: usize) {
+        self.lookahead_size = lookahead_size;
+        self.delay_buffer.clear();
+        self.minimum_gains.clear();
+        self.averaged_gains.clear();
+        self.gains.clear();
+    }
+
+    //resets on lookahead changes, returns the linear ceiling and the release factor
+    fn prepare(&mut self, sample_timing: &SampleTiming) -> (f32, f32) {
+        let lookahead_size = self.latency(sample_timing.sample_rate);
+        if lookahead_size != self.lookahead_size {
+            self.reset(lookahead_size);
+        }
+        (from_db(self.ceiling), smoothing_factor(self.release, sample_timing.sample_rate))
+    }
+
+    fn limit(&mut self, poly_sample: PolySample, ceiling: f32, release_factor: f32) -> PolySample {
+        let required_gain = |sample: f32| (ceiling / sample.abs().max(1e-9)).min(1.0);
+        let required_gains = if self.stereo_link {
+            poly_sample!([poly_sample.iter().copied().map(required_gain).fold(1.0, f32::min)])
+        } else {
+            PolySample(poly_sample.iter().copied().map(required_gain).collect())
+        };
+        self.minimum_gains.resize(required_gains.len(), VecDeque::new());
+        self.averaged_gains.resize(required_gains.len(), (VecDeque::new(), 0.0));
+        self.gains.resize(required_gains.len(), 1.0);
+
+        //moving minimum, then moving average over the lookahead, so the gain is low enough
+        //when the delayed peak arrives
+        let lookahead_size = self.lookahead_size;
+        let clock = self.clock;
+        self.clock += 1;
+        for (((required_gain, minimum_gains), (averaged_gains, sum)), gain) in required_gains
+            .iter()
+            .copied()
+            .zip(&mut self.minimum_gains)
+            .zip(&mut self.averaged_gains)
+            .zip(&mut self.gains)
+        {
+            while minimum_gains.back().is_some_and(|(_, gain)| *gain >= required_gain) {
+                minimum_gains.pop_back();
+            }
+            minimum_gains.push_back((clock, required_gain));
+            while minimum_gains.front().is_some_and(|(time, _)| time + lookahead_size < clock) {
+                minimum_gains.pop_front();
+            }
+            let minimum_gain = minimum_gains.front().unwrap().1;
+
+            averaged_gains.push_back(minimum_gain);
+            *sum += minimum_gain;
+            if averaged_gains.len() > lookahead_size + 1 {
+                *sum -= averaged_gains.pop_front().unwrap();
+            }
+            //the average of a partly filled window assumes unity gain for the missing values
+            let missing = (lookahead_size + 1 - averaged_gains.len()) as f32;
+            let target = (*sum + missing) / (lookahead_size + 1) as f32;
+
+            *gain = if target < *gain { target } else { *gain + (target - *gain) * release_factor };
+        }
+
+        self.delay_buffer.push_back(poly_sample);
+        let mut output = if self.delay_buffer.len() > lookahead_size {
+            self.delay_buffer.pop_front().unwrap()
+        } else {
+            PolySample(self.delay_buffer[0].iter().map(|_| 0.0).collect())
+        };
+        for (channel, sample) in output.iter_mut().enumerate() {
+            let gain = self.gains.get(channel).or_else(|| self.gains.first()).copied();
+            *sample *= gain.unwrap_or(1.0);
+        }
+        output
+    }
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Self::new(-0.3, 0.005, 0.05)
+    }
+}
+
+impl Effect for Limiter {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        let (ceiling, release_factor) = self.prepare(sample_timing);
+        self.limit(poly_sample, ceiling, release_factor)
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let (ceiling, release_factor) = self.prepare(sample_timing);
+        for poly_sample in buffer {
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.limit(input, ceiling, release_factor);
+        }
+    }
+}
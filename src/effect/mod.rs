@@ -1,12 +1,75 @@
+mod biquad;
+mod bitcrusher;
+mod chorus;
+mod compressor;
+mod convolver;
 mod delay;
+mod delay_line;
+mod distortion;
+mod downsampler;
+mod ducker;
+mod dynamics;
+mod expander;
+mod flanger;
+mod ladder_filter;
 mod lag;
+mod limiter;
+mod modulation;
+mod noise_gate;
 mod oscilloscope;
+mod panner;
+mod phaser;
+mod reverb;
+mod state_variable_filter;
+mod stereo_width;
 
-use crate::{PolySample, SampleTiming};
+use crate::{poly_sample, PolySample, SampleTiming};
+pub use biquad::{Biquad, BiquadType};
+pub use bitcrusher::Bitcrusher;
+pub use chorus::Chorus;
+pub use compressor::Compressor;
+pub use convolver::Convolver;
 pub use delay::Delay;
+pub use delay_line::DelayLine;
+pub use distortion::{Distortion, TransferCurve};
+pub use downsampler::Downsampler;
+pub use ducker::Ducker;
+pub(crate) use dynamics::from_db;
+pub use dynamics::Detection;
+pub use expander::Expander;
+pub use flanger::Flanger;
+pub use ladder_filter::LadderFilter;
 pub use lag::Lag;
+pub use limiter::Limiter;
+pub use noise_gate::NoiseGate;
 pub use oscilloscope::Oscilloscope;
+pub use panner::{PanLaw, Panner};
+pub use phaser::Phaser;
+pub use reverb::Reverb;
+pub use state_variable_filter::{StateVariableFilter, StateVariableOutput, StateVariableOutputs};
+pub use stereo_width::StereoWidth;
 
 pub trait Effect: Send {
     fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample;
+
+    /// Processes consecutive samples in place, starting at `sample_timing`.
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let mut sample_timing = *sample_timing;
+        for poly_sample in buffer {
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.process(&sample_timing, input);
+            sample_timing.tick();
+        }
+    }
+}
+
+/// Effect with a secondary key input controlling how the main input is processed,
+/// e.g. a compressor keyed by a kick drum.
+pub trait SidechainEffect: Effect {
+    fn process_sidechain(
+        &mut self,
+        sample_timing: &SampleTiming,
+        poly_sample: PolySample,
+        key: PolySample,
+    ) -> PolySample;
 }
@@ -0,0 +1,25 @@
+use crate::{
+    generator::{Generator, Phase},
+    SampleTiming,
+};
+use std::f32::consts::PI;
+
+/// Advances the LFO of a modulation effect by one sample.
+///
+/// Returns the modulation from `-1.0` to `1.0` at a phase offset, from the internal sine LFO
+/// or, if set, from the first channel of the external `modulator`, which ignores the offset.
+pub(super) fn lfo(
+    phase: &mut Phase,
+    modulator: &mut Option<Box<dyn Generator>>,
+    sample_timing: &SampleTiming,
+    rate: f32,
+) -> impl Fn(f32) -> f32 {
+    let external = modulator
+        .as_mut()
+        .map(|modulator| modulator.generate(sample_timing).first().copied().unwrap_or(0.0));
+    let (phase, _) = phase.advance(sample_timing, rate);
+    move |offset| match external {
+        Some(modulation) => modulation,
+        None => ((phase + offset) * 2.0 * PI).sin(),
+    }
+}
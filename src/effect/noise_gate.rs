@@ -0,0 +1,106 @@
+use super::{
+    dynamics::{Dynamics, Factors},
+    Detection, Effect, SidechainEffect,
+};
+use crate::{poly_sample, PolySample, SampleTiming};
+
+/// Mutes the signal while its level is below `threshold`.
+#[derive(Clone)]
+pub struct NoiseGate {
+    /// Level in dB above which the gate opens.
+    pub threshold: f32,
+    /// Gain reduction in dB of the closed gate.
+    pub range: f32,
+    /// Time (in seconds) it takes the gate to mostly open.
+    pub attack: f32,
+    /// Time (in seconds) the gate stays open after the level fell below the threshold.
+    pub hold: f32,
+    /// Time (in seconds) it takes the gate to mostly close.
+    pub release: f32,
+    pub detection: Detection,
+    /// Detects the level of all channels together, so the stereo image doesn't shift.
+    pub stereo_link: bool,
+    hold_counters: Vec<usize>,
+    dynamics: Dynamics,
+}
+
+impl NoiseGate {
+    pub fn new(threshold: f32, attack: f32, hold: f32, release: f32) -> Self {
+        Self {
+            threshold,
+            range: 80.0,
+            attack,
+            hold,
+            release,
+            detection: Detection::Peak,
+            stereo_link: true,
+            hold_counters: Vec::new(),
+            dynamics: Dynamics::default(),
+        }
+    }
+
+    /// Current gain reduction in dB, e.g. for metering.
+    pub fn gain_reduction(&self) -> f32 {
+        self.dynamics.gain_reduction()
+    }
+}
+
+impl Default for NoiseGate {
+    fn default() -> Self {
+        Self::new(-50.0, 0.001, 0.05, 0.05)
+    }
+}
+
+impl Effect for NoiseGate {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        let key = poly_sample.clone();
+        self.process_sidechain(sample_timing, poly_sample, key)
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let factors = Factors::new(sample_timing.sample_rate, self.attack, self.release);
+        let hold_size = sample_timing.duration_to_sample_count(self.hold);
+        for poly_sample in buffer {
+            let key = poly_sample.clone();
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.process_with(&factors, hold_size, input, key);
+        }
+    }
+}
+
+impl SidechainEffect for NoiseGate {
+    fn process_sidechain(
+        &mut self,
+        sample_timing: &SampleTiming,
+        poly_sample: PolySample,
+        key: PolySample,
+    ) -> PolySample {
+        let factors = Factors::new(sample_timing.sample_rate, self.attack, self.release);
+        let hold_size = sample_timing.duration_to_sample_count(self.hold);
+        self.process_with(&factors, hold_size, poly_sample, key)
+    }
+}
+
+impl NoiseGate {
+    fn process_with(
+        &mut self,
+        factors: &Factors,
+        hold_size: usize,
+        poly_sample: PolySample,
+        key: PolySample,
+    ) -> PolySample {
+        let mut targets = self.dynamics.levels(factors, &key, self.detection, self.stereo_link);
+        //samples since the level was above the threshold, starting closed
+        self.hold_counters.resize(targets.len(), hold_size + 1);
+        for (level, hold_counter) in targets.iter_mut().zip(&mut self.hold_counters) {
+            if *level >= self.threshold {
+                *hold_counter = 0;
+            } else {
+                *hold_counter = (*hold_counter + 1).min(hold_size + 1);
+            }
+            *level = if *hold_counter <= hold_size { 0.0 } else { -self.range };
+        }
+        self.dynamics.smooth(factors, &targets, true);
+        self.dynamics.apply(poly_sample, 0.0)
+    }
+}
@@ -0,0 +1,97 @@
+use super::Effect;
+use crate::{poly_sample, PolySample, SampleTiming};
+use std::f32::consts::FRAC_PI_2;
+
+/// How the volume of the channels changes with the pan position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PanLaw {
+    /// Constant amplitude, -6 dB per channel in the center, which sounds quieter.
+    Linear,
+    /// Constant power, -3 dB per channel in the center.
+    ConstantPower,
+    /// Compromise between the other laws, -4.5 dB per channel in the center.
+    Compromise,
+}
+
+impl PanLaw {
+    /// Gains of the left and right channel at `pan` from `-1.0` (left) to `1.0` (right).
+    pub fn gains(self, pan: f32) -> (f32, f32) {
+        let position = (pan.clamp(-1.0, 1.0) + 1.0) / 2.0;
+        let linear = (1.0 - position, position);
+        let (sin, cos) = (position * FRAC_PI_2).sin_cos();
+        match self {
+            PanLaw::Linear => linear,
+            PanLaw::ConstantPower => (cos, sin),
+            PanLaw::Compromise => ((linear.0 * cos).sqrt(), (linear.1 * sin).sqrt()),
+        }
+    }
+}
+
+/// Positions the input in the stereo field.
+///
+/// Stereo input is summed to mono before panning, unless `balance` is set.
+#[derive(Clone)]
+pub struct Panner {
+    /// From `-1.0` (left) to `1.0` (right).
+    pub pan: f32,
+    pub law: PanLaw,
+    /// Only attenuates the channel opposite to `pan` of stereo input, keeping the center at full volume.
+    pub balance: bool,
+}
+
+impl Panner {
+    pub fn new(pan: f32, law: PanLaw) -> Self {
+        Self {
+            pan,
+            law,
+            balance: false,
+        }
+    }
+}
+
+impl Default for Panner {
+    fn default() -> Self {
+        Self::new(0.0, PanLaw::ConstantPower)
+    }
+}
+
+impl Effect for Panner {
+    fn process(&mut self, _sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        if PolySample::MAX_CHANNELS < 2 || poly_sample.is_empty() {
+            return poly_sample;
+        }
+        let (left_gain, right_gain) = self.law.gains(self.pan);
+        if self.balance && poly_sample.len() >= 2 {
+            let (center_left, center_right) = self.law.gains(0.0);
+            let mut poly_sample = poly_sample;
+            poly_sample[0] *= (left_gain / center_left).min(1.0);
+            poly_sample[1] *= (right_gain / center_right).min(1.0);
+            poly_sample
+        } else {
+            let mono = poly_sample.iter().sum::<f32>() / poly_sample.len() as f32;
+            poly_sample!([mono * left_gain, mono * right_gain])
+        }
+    }
+
+    fn process_block(&mut self, _sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        if PolySample::MAX_CHANNELS < 2 {
+            return;
+        }
+        let (left_gain, right_gain) = self.law.gains(self.pan);
+        let (center_left, center_right) = self.law.gains(0.0);
+        let balance_gains =
+            ((left_gain / center_left).min(1.0), (right_gain / center_right).min(1.0));
+        for poly_sample in buffer {
+            if poly_sample.is_empty() {
+                continue;
+            }
+            if self.balance && poly_sample.len() >= 2 {
+                poly_sample[0] *= balance_gains.0;
+                poly_sample[1] *= balance_gains.1;
+            } else {
+                let mono = poly_sample.iter().sum::<f32>() / poly_sample.len() as f32;
+                *poly_sample = poly_sample!([mono * left_gain, mono * right_gain]);
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use super::{modulation::lfo, Effect};
+use crate::{
+    generator::{Generator, Phase},
+    poly_sample, PolySample, SampleTiming,
+};
+use std::f32::consts::PI;
+
+#[derive(Clone, Default)]
+struct ChannelState {
+    allpasses: Vec<f32>,
+    last_output: f32,
+}
+
+/// Sweeping notches made of cascaded first order all-pass filters mixed with the input.
+///
+/// The LFO of the second channel is a quarter period ahead for a wider stereo image.
+pub struct Phaser {
+    /// Number of all-pass filters, every two stages add a notch.
+    pub stages: usize,
+    /// LFO frequency in Hz.
+    pub rate: f32,
+    /// Lowest break frequency (in Hz) of the all-pass filters.
+    pub min_frequency: f32,
+    /// Highest break frequency (in Hz) of the all-pass filters.
+    pub max_frequency: f32,
+    /// From `-1.0` to `1.0`, emphasizes the notches.
+    pub feedback: f32,
+    pub wet: f32,
+    pub dry: f32,
+    /// Replaces the internal LFO, should generate values from `-1.0` to `1.0`.
+    pub modulator: Option<Box<dyn Generator>>,
+    lfo_phase: Phase,
+    states: Vec<ChannelState>,
+}
+
+impl Phaser {
+    pub fn new(stages: usize, rate: f32, min_frequency: f32, max_frequency: f32) -> Self {
+        Self {
+            stages,
+            rate,
+            min_frequency,
+            max_frequency,
+            feedback: 0.0,
+            wet: 1.0,
+            dry: 1.0,
+            modulator: None,
+            lfo_phase: Phase::default(),
+            states: Vec::new(),
+        }
+    }
+}
+
+impl Default for Phaser {
+    fn default() -> Self {
+        Self::new(4, 0.5, 200.0, 2000.0)
+    }
+}
+
+impl Effect for Phaser {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        self.prepare(poly_sample.len());
+        self.process_with(sample_timing, poly_sample)
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let channels = buffer.iter().map(|poly_sample| poly_sample.len()).max().unwrap_or(0);
+        self.prepare(channels);
+        let mut sample_timing = *sample_timing;
+        for poly_sample in buffer {
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.process_with(&sample_timing, input);
+            sample_timing.tick();
+        }
+    }
+}
+
+impl Phaser {
+    //adds the states of `channels` channels, with `stages` all-pass filters each
+    fn prepare(&mut self, channels: usize) {
+        if self.states.len() < channels {
+            self.states.resize(channels, ChannelState::default());
+        }
+        for state in self.states.iter_mut().take(channels) {
+            state.allpasses.resize(self.stages, 0.0);
+        }
+    }
+
+    fn process_with(
+        &mut self,
+        sample_timing: &SampleTiming,
+        mut poly_sample: PolySample,
+    ) -> PolySample {
+        let lfo = lfo(&mut self.lfo_phase, &mut self.modulator, sample_timing, self.rate);
+        let sample_rate = sample_timing.sample_rate;
+        //exponential sweep, so the notches move evenly in pitch
+        let (min, max) = (self.min_frequency.max(1.0), self.max_frequency.max(1.0));
+
+        for (channel, (sample, state)) in poly_sample.iter_mut().zip(&mut self.states).enumerate() {
+            let offset = if channel % 2 == 1 { 0.25 } else { 0.0 };
+            let frequency =
+                (min * (max / min).powf((lfo(offset) + 1.0) / 2.0)).min(sample_rate * 0.49);
+            let tan = (PI * frequency / sample_rate).tan();
+            let coefficient = (tan - 1.0) / (tan + 1.0);
+
+            let mut output = *sample + state.last_output * self.feedback;
+            for allpass in &mut state.allpasses {
+                let input = output;
+                output = coefficient * input + *allpass;
+                *allpass = input - coefficient * output;
+            }
+            state.last_output = output;
+            *sample = *sample * self.dry + output * self.wet;
+        }
+        poly_sample
+    }
+}
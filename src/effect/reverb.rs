@@ -0,0 +1,185 @@
+use super::Effect;
+use crate::{poly_sample, PolySample, SampleTiming};
+use std::collections::VecDeque;
+
+//tunings of Freeverb, in samples at 44100 Hz
+const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
+const STEREO_SPREAD: usize = 23;
+const FIXED_GAIN: f32 = 0.015;
+
+#[derive(Clone)]
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    filter_store: f32,
+}
+
+impl Comb {
+    fn new(size: usize) -> Self {
+        Self {
+            buffer: vec![0.0; size.max(1)],
+            index: 0,
+            filter_store: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filter_store = output * (1.0 - damping) + self.filter_store * damping;
+        self.buffer[self.index] = input + self.filter_store * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+#[derive(Clone)]
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Allpass {
+    fn new(size: usize) -> Self {
+        Self {
+            buffer: vec![0.0; size.max(1)],
+            index: 0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let buffered = self.buffer[self.index];
+        self.buffer[self.index] = input + buffered * 0.5;
+        self.index = (self.index + 1) % self.buffer.len();
+        buffered - input
+    }
+}
+
+#[derive(Clone)]
+struct Channel {
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+}
+
+impl Channel {
+    fn new(sample_rate: f32, spread: usize) -> Self {
+        let scale = |tuning: usize| ((tuning + spread) as f32 * sample_rate / 44100.0) as usize;
+        Self {
+            combs: COMB_TUNINGS.iter().map(|tuning| Comb::new(scale(*tuning))).collect(),
+            allpasses: ALLPASS_TUNINGS.iter().map(|tuning| Allpass::new(scale(*tuning))).collect(),
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let mut output =
+            self.combs.iter_mut().map(|comb| comb.process(input, feedback, damping)).sum();
+        for allpass in &mut self.allpasses {
+            output = allpass.process(output);
+        }
+        output
+    }
+}
+
+/// Freeverb-style algorithmic reverb.
+///
+/// Mono input is turned into stereo output.
+#[derive(Clone)]
+pub struct Reverb {
+    /// From `0.0` to `1.0`, bigger rooms reverberate longer.
+    pub room_size: f32,
+    /// From `0.0` to `1.0`, higher values absorb high frequencies faster.
+    pub damping: f32,
+    /// Time (in seconds) before the reverb starts.
+    pub pre_delay: f32,
+    /// From `0.0` (mono) to `1.0` (full stereo).
+    pub width: f32,
+    pub wet: f32,
+    pub dry: f32,
+    channels: Option<(f32, [Channel; 2])>,
+    pre_delay_buffer: VecDeque<f32>,
+}
+
+impl Reverb {
+    pub fn new(
+        room_size: f32,
+        damping: f32,
+        pre_delay: f32,
+        width: f32,
+        wet: f32,
+        dry: f32,
+    ) -> Self {
+        Self {
+            room_size,
+            damping,
+            pre_delay,
+            width,
+            wet,
+            dry,
+            channels: None,
+            pre_delay_buffer: VecDeque::new(),
+        }
+    }
+}
+
+impl Default for Reverb {
+    fn default() -> Self {
+        Self::new(0.5, 0.5, 0.0, 1.0, 0.3, 1.0)
+    }
+}
+
+impl Effect for Reverb {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        let pre_delay_size = self.prepare(sample_timing);
+        self.reverberate(poly_sample, pre_delay_size)
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let pre_delay_size = self.prepare(sample_timing);
+        for poly_sample in buffer {
+            let input = std::mem::replace(poly_sample, poly_sample!());
+            *poly_sample = self.reverberate(input, pre_delay_size);
+        }
+    }
+}
+
+impl Reverb {
+    //creates the channels for the sample rate, returns the pre-delay in samples
+    fn prepare(&mut self, sample_timing: &SampleTiming) -> usize {
+        let sample_rate = sample_timing.sample_rate;
+        if self.channels.as_ref().map(|(channels_sample_rate, _)| *channels_sample_rate)
+            != Some(sample_rate)
+        {
+            let channels = [Channel::new(sample_rate, 0), Channel::new(sample_rate, STEREO_SPREAD)];
+            self.channels = Some((sample_rate, channels));
+        }
+        sample_timing.duration_to_sample_count(self.pre_delay)
+    }
+
+    fn reverberate(&mut self, poly_sample: PolySample, pre_delay_size: usize) -> PolySample {
+        let (_, channels) = self.channels.as_mut().unwrap();
+        let dry_left = poly_sample.first().copied().unwrap_or(0.0);
+        let dry_right = poly_sample.get(1).copied().unwrap_or(dry_left);
+
+        self.pre_delay_buffer.push_back((dry_left + dry_right) * FIXED_GAIN);
+        let mut input = 0.0;
+        while self.pre_delay_buffer.len() > pre_delay_size {
+            input = self.pre_delay_buffer.pop_front().unwrap();
+        }
+
+        let feedback = self.room_size * 0.28 + 0.7;
+        let damping = self.damping * 0.4;
+        let left = channels[0].process(input, feedback, damping);
+        let right = channels[1].process(input, feedback, damping);
+
+        let wet1 = self.wet * (self.width / 2.0 + 0.5);
+        let wet2 = self.wet * ((1.0 - self.width) / 2.0);
+        let output_left = left * wet1 + right * wet2 + dry_left * self.dry;
+        let output_right = right * wet1 + left * wet2 + dry_right * self.dry;
+
+        if PolySample::MAX_CHANNELS >= 2 {
+            poly_sample!([output_left, output_right])
+        } else {
+            poly_sample!([(output_left + output_right) / 2.0])
+        }
+    }
+}
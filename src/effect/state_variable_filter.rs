@@ -0,0 +1,136 @@
+use super::Effect;
+use crate::{PolySample, SampleTiming};
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StateVariableOutput {
+    LowPass,
+    BandPass,
+    HighPass,
+    Notch,
+}
+
+/// All outputs of a [`StateVariableFilter`], calculated at once.
+#[derive(Clone, Debug)]
+pub struct StateVariableOutputs {
+    pub low_pass: PolySample,
+    pub band_pass: PolySample,
+    pub high_pass: PolySample,
+    pub notch: PolySample,
+}
+
+/// Zero-delay-feedback state-variable filter, stays stable when `cutoff` is modulated every sample.
+#[derive(Clone)]
+pub struct StateVariableFilter {
+    /// Output returned by [`process`](Effect::process).
+    pub output: StateVariableOutput,
+    /// Cutoff frequency in Hz.
+    pub cutoff: f32,
+    pub q: f32,
+    states: Vec<[f32; 2]>,
+}
+
+impl StateVariableFilter {
+    pub fn new(output: StateVariableOutput, cutoff: f32, q: f32) -> Self {
+        Self {
+            output,
+            cutoff,
+            q,
+            states: Vec::new(),
+        }
+    }
+
+    /// Filters `poly_sample`, returning all outputs.
+    pub fn process_all(
+        &mut self,
+        sample_timing: &SampleTiming,
+        poly_sample: PolySample,
+    ) -> StateVariableOutputs {
+        if self.states.len() < poly_sample.len() {
+            self.states.resize(poly_sample.len(), [0.0; 2]);
+        }
+
+        let [k, a1, a2, a3] = self.coefficients(sample_timing.sample_rate);
+
+        let mut outputs = StateVariableOutputs {
+            low_pass: poly_sample.clone(),
+            band_pass: poly_sample.clone(),
+            high_pass: poly_sample.clone(),
+            notch: poly_sample.clone(),
+        };
+        for (channel, (input, state)) in poly_sample.iter().zip(self.states.iter_mut()).enumerate()
+        {
+            let v3 = input - state[1];
+            let v1 = a1 * state[0] + a2 * v3;
+            let v2 = state[1] + a2 * state[0] + a3 * v3;
+            state[0] = 2.0 * v1 - state[0];
+            state[1] = 2.0 * v2 - state[1];
+
+            let high_pass = input - k * v1 - v2;
+            outputs.low_pass[channel] = v2;
+            outputs.band_pass[channel] = v1;
+            outputs.high_pass[channel] = high_pass;
+            outputs.notch[channel] = v2 + high_pass;
+        }
+        outputs
+    }
+
+    fn coefficients(&self, sample_rate: f32) -> [f32; 4] {
+        let cutoff = self.cutoff.max(1.0).min(sample_rate * 0.49);
+        let g = (PI * cutoff / sample_rate).tan();
+        let k = 1.0 / self.q.max(0.01);
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        [k, a1, a2, a3]
+    }
+}
+
+impl Default for StateVariableFilter {
+    fn default() -> Self {
+        Self::new(StateVariableOutput::LowPass, 1000.0, std::f32::consts::FRAC_1_SQRT_2)
+    }
+}
+
+impl Effect for StateVariableFilter {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        let outputs = self.process_all(sample_timing, poly_sample);
+        match self.output {
+            StateVariableOutput::LowPass => outputs.low_pass,
+            StateVariableOutput::BandPass => outputs.band_pass,
+            StateVariableOutput::HighPass => outputs.high_pass,
+            StateVariableOutput::Notch => outputs.notch,
+        }
+    }
+
+    fn process_block(&mut self, sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let channels = buffer.iter().map(|poly_sample| poly_sample.len()).max().unwrap_or(0);
+        if self.states.len() < channels {
+            self.states.resize(channels, [0.0; 2]);
+        }
+        let [k, a1, a2, a3] = self.coefficients(sample_timing.sample_rate);
+        let output = self.output;
+        for (channel, state) in self.states.iter_mut().enumerate().take(channels) {
+            let [mut state0, mut state1] = *state;
+            for poly_sample in buffer.iter_mut() {
+                if let Some(sample) = poly_sample.get_mut(channel) {
+                    let input = *sample;
+                    let v3 = input - state1;
+                    let v1 = a1 * state0 + a2 * v3;
+                    let v2 = state1 + a2 * state0 + a3 * v3;
+                    state0 = 2.0 * v1 - state0;
+                    state1 = 2.0 * v2 - state1;
+
+                    let high_pass = input - k * v1 - v2;
+                    *sample = match output {
+                        StateVariableOutput::LowPass => v2,
+                        StateVariableOutput::BandPass => v1,
+                        StateVariableOutput::HighPass => high_pass,
+                        StateVariableOutput::Notch => v2 + high_pass,
+                    };
+                }
+            }
+            *state = [state0, state1];
+        }
+    }
+}
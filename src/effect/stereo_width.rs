@@ -0,0 +1,48 @@
+use super::Effect;
+use crate::{PolySample, SampleTiming};
+
+/// Narrows or widens stereo input by scaling its side signal.
+#[derive(Clone)]
+pub struct StereoWidth {
+    /// `0.0` is mono, `1.0` unchanged and values above `1.0` widen.
+    pub width: f32,
+}
+
+impl StereoWidth {
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+        }
+    }
+}
+
+impl Default for StereoWidth {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl Effect for StereoWidth {
+    fn process(
+        &mut self,
+        _sample_timing: &SampleTiming,
+        mut poly_sample: PolySample,
+    ) -> PolySample {
+        if poly_sample.len() >= 2 {
+            poly_sample.encode_mid_side();
+            poly_sample[1] *= self.width;
+            poly_sample.decode_mid_side();
+        }
+        poly_sample
+    }
+
+    fn process_block(&mut self, _sample_timing: &SampleTiming, buffer: &mut [PolySample]) {
+        let (mid_gain, side_gain) = (0.5, 0.5 * self.width);
+        for poly_sample in buffer.iter_mut().filter(|poly_sample| poly_sample.len() >= 2) {
+            let (left, right) = (poly_sample[0], poly_sample[1]);
+            let (mid, side) = (mid_gain * (left + right), side_gain * (left - right));
+            poly_sample[0] = mid + side;
+            poly_sample[1] = mid - side;
+        }
+    }
+}
@@ -1,6 +1,19 @@
-use super::Generator;
+use super::{Curve, Generator};
 use crate::prelude::*;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Stage {
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// Attack-Decay-Sustain-Release envelope.
+///
+/// By default the envelope has a fixed duration, where `sustain` is the time the sustain level is held.
+/// After [`gate_on`](Self::gate_on) it's gated instead, holding the sustain level until [`gate_off`](Self::gate_off).
 #[derive(Clone)]
 pub struct AdsrGenerator {
     pub attack: f32,
@@ -8,6 +21,14 @@ pub struct AdsrGenerator {
     pub sustain_level: f32,
     pub sustain: f32,
     pub release: f32,
+    pub attack_curve: Curve,
+    pub decay_curve: Curve,
+    pub release_curve: Curve,
+    gated: bool,
+    stage: Stage,
+    stage_start_level: f32,
+    stage_progress: f32,
+    level: f32,
 }
 
 impl AdsrGenerator {
@@ -18,30 +39,84 @@ impl AdsrGenerator {
             sustain,
             sustain_level,
             release,
+            attack_curve: Curve::Linear,
+            decay_curve: Curve::Linear,
+            release_curve: Curve::Linear,
+            gated: false,
+            stage: Stage::Idle,
+            stage_start_level: 0.0,
+            stage_progress: 0.0,
+            level: 0.0,
         }
     }
 
     pub fn total_duration(&self) -> f32 {
         self.attack + self.decay + self.sustain + self.release
     }
-}
 
-impl Default for AdsrGenerator {
-    fn default() -> Self {
-        Self::new(0.0, 0.0, 1.0, 0.0, 0.0)
+    /// Starts the attack from the current level and switches to gated mode.
+    pub fn gate_on(&mut self) {
+        self.gated = true;
+        self.enter_stage(Stage::Attack);
     }
-}
 
-impl Generator for AdsrGenerator {
-    fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample {
+    /// Starts the release from the current level.
+    pub fn gate_off(&mut self) {
+        if self.gated && self.stage != Stage::Idle {
+            self.enter_stage(Stage::Release);
+        }
+    }
+
+    pub fn is_gated(&self) -> bool {
+        self.gated
+    }
+
+    /// Returns `true` while a gated envelope hasn't finished its release.
+    pub fn is_active(&self) -> bool {
+        self.gated && self.stage != Stage::Idle
+    }
+
+    /// Level of the last generated sample.
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    fn enter_stage(&mut self, stage: Stage) {
+        self.stage = stage;
+        self.stage_start_level = self.level;
+        self.stage_progress = 0.0;
+    }
+
+    fn generate_gated(&mut self, sample_timing: &SampleTiming) -> f32 {
+        let (duration, target_level, curve, next_stage) = match self.stage {
+            Stage::Idle => return 0.0,
+            Stage::Sustain => return self.sustain_level,
+            Stage::Attack => (self.attack, 1.0, self.attack_curve, Stage::Decay),
+            Stage::Decay => (self.decay, self.sustain_level, self.decay_curve, Stage::Sustain),
+            Stage::Release => (self.release, 0.0, self.release_curve, Stage::Idle),
+        };
+
+        self.stage_progress += 1.0 / (duration * sample_timing.sample_rate);
+        if self.stage_progress >= 1.0 || duration <= 0.0 {
+            self.level = target_level;
+            self.enter_stage(next_stage);
+            target_level
+        } else {
+            self.stage_start_level
+                + (target_level - self.stage_start_level) * curve.shape(self.stage_progress)
+        }
+    }
+
+    fn generate_timed(&self, sample_timing: &SampleTiming) -> f32 {
         let mut sample_clock = sample_timing.sample_clock();
 
-        let value = if sample_clock < self.attack {
-            sample_clock / self.attack
+        if sample_clock < self.attack {
+            self.attack_curve.shape(sample_clock / self.attack)
         } else {
             sample_clock -= self.attack;
             if sample_clock < self.decay {
-                1.0 - ((sample_clock / self.decay) * (1.0 - self.sustain_level))
+                1.0 - (self.decay_curve.shape(sample_clock / self.decay)
+                    * (1.0 - self.sustain_level))
             } else {
                 sample_clock -= self.decay;
                 if sample_clock < self.sustain {
@@ -49,13 +124,57 @@ impl Generator for AdsrGenerator {
                 } else {
                     sample_clock -= self.sustain;
                     if sample_clock < self.release {
-                        (1.0 - ((sample_clock) / self.release)) * self.sustain_level
+                        (1.0 - self.release_curve.shape(sample_clock / self.release))
+                            * self.sustain_level
                     } else {
                         0.0
                     }
                 }
             }
+        }
+    }
+}
+
+impl Default for AdsrGenerator {
+    fn default() -> Self {
+        Self::new(0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+}
+
+impl Generator for AdsrGenerator {
+    fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        let value = if self.gated {
+            self.generate_gated(sample_timing)
+        } else {
+            self.generate_timed(sample_timing)
         };
+        self.level = value;
         poly_sample!([value])
     }
+
+    fn generate_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        let mut sample_timing = *sample_timing;
+        //the mode can't change during a block
+        if self.gated {
+            for poly_sample in output.iter_mut() {
+                *poly_sample = poly_sample!([self.generate_gated(&sample_timing)]);
+                sample_timing.tick();
+            }
+        } else {
+            for poly_sample in output.iter_mut() {
+                *poly_sample = poly_sample!([self.generate_timed(&sample_timing)]);
+                sample_timing.tick();
+            }
+        }
+        if let Some(poly_sample) = output.last() {
+            self.level = poly_sample[0];
+        }
+    }
+
+    /// Switches back to the fixed duration mode.
+    fn reset(&mut self) {
+        self.gated = false;
+        self.stage = Stage::Idle;
+        self.level = 0.0;
+    }
 }
@@ -0,0 +1,14 @@
+/// Polynomial band-limited step, smoothing the discontinuity of a waveform at `phase` 0.
+///
+/// `phase` is in `[0, 1)` and `phase_increment` is the frequency divided by the sample rate.
+pub(crate) fn poly_blep(phase: f32, phase_increment: f32) -> f32 {
+    if phase < phase_increment {
+        let t = phase / phase_increment;
+        2.0 * t - t * t - 1.0
+    } else if phase > 1.0 - phase_increment {
+        let t = (phase - 1.0) / phase_increment;
+        t * t + 2.0 * t + 1.0
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,167 @@
+use super::{Curve, Generator};
+use crate::prelude::*;
+
+/// Stage of a [`BreakpointEnvelope`], moving to `level` over `time` seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Breakpoint {
+    pub time: f32,
+    pub level: f32,
+    pub curve: Curve,
+}
+
+impl Breakpoint {
+    pub fn new(time: f32, level: f32, curve: Curve) -> Self {
+        Self {
+            time,
+            level,
+            curve,
+        }
+    }
+}
+
+/// Envelope made of an arbitrary list of [`Breakpoint`]s, e.g. for AHDSR, DADSR or looping modulation envelopes.
+///
+/// Without calling [`gate_on`](Self::gate_on) all breakpoints are played through once.
+/// While the gate is open, the envelope holds at the `sustain_point` or repeats the stages between the `loop_points`.
+#[derive(Clone)]
+pub struct BreakpointEnvelope {
+    pub start_level: f32,
+    pub breakpoints: Vec<Breakpoint>,
+    /// Index of the breakpoint whose level is held while the gate is open.
+    pub sustain_point: Option<usize>,
+    /// Indices of the first and last breakpoint that are repeated while the gate is open.
+    pub loop_points: Option<(usize, usize)>,
+    gate: bool,
+    index: usize,
+    holding: bool,
+    stage_start_level: f32,
+    stage_progress: f32,
+    level: f32,
+}
+
+impl BreakpointEnvelope {
+    pub fn new(start_level: f32, breakpoints: Vec<Breakpoint>) -> Self {
+        Self {
+            start_level,
+            breakpoints,
+            sustain_point: None,
+            loop_points: None,
+            gate: false,
+            index: 0,
+            holding: false,
+            stage_start_level: start_level,
+            stage_progress: 0.0,
+            level: start_level,
+        }
+    }
+
+    /// Attack-Hold-Decay-Sustain-Release envelope, sustaining until [`gate_off`](Self::gate_off).
+    pub fn ahdsr(attack: f32, hold: f32, decay: f32, sustain_level: f32, release: f32) -> Self {
+        let mut envelope = Self::new(
+            0.0,
+            vec![
+                Breakpoint::new(attack, 1.0, Curve::Linear),
+                Breakpoint::new(hold, 1.0, Curve::Linear),
+                Breakpoint::new(decay, sustain_level, Curve::Logarithmic),
+                Breakpoint::new(release, 0.0, Curve::Logarithmic),
+            ],
+        );
+        envelope.sustain_point = Some(2);
+        envelope
+    }
+
+    /// Starts the envelope from the current level and opens the gate.
+    pub fn gate_on(&mut self) {
+        self.gate = true;
+        self.enter_stage(0);
+    }
+
+    /// Closes the gate, continuing after the sustain point or loop from the current level.
+    pub fn gate_off(&mut self) {
+        self.gate = false;
+        let release_index = self.sustain_point.or_else(|| self.loop_points.map(|(_, end)| end));
+        if let Some(release_index) = release_index {
+            if self.index <= release_index {
+                self.enter_stage(release_index + 1);
+            }
+        }
+    }
+
+    pub fn total_duration(&self) -> f32 {
+        self.breakpoints.iter().map(|breakpoint| breakpoint.time).sum()
+    }
+
+    /// Returns `true` when all stages have been played through.
+    pub fn is_finished(&self) -> bool {
+        self.index >= self.breakpoints.len()
+    }
+
+    /// Level of the last generated sample.
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    fn enter_stage(&mut self, index: usize) {
+        self.index = index;
+        self.holding = false;
+        self.stage_start_level = self.level;
+        self.stage_progress = 0.0;
+    }
+
+    fn next_level(&mut self, sample_rate: f32) -> f32 {
+        if let (Some(breakpoint), false) = (self.breakpoints.get(self.index), self.holding) {
+            self.stage_progress += 1.0 / (breakpoint.time * sample_rate);
+            if self.stage_progress >= 1.0 || breakpoint.time <= 0.0 {
+                self.level = breakpoint.level;
+                self.next_stage();
+            } else {
+                self.level = self.stage_start_level
+                    + (breakpoint.level - self.stage_start_level)
+                        * breakpoint.curve.shape(self.stage_progress);
+            }
+        }
+        self.level
+    }
+
+    /// Called when the breakpoint at `self.index` was reached.
+    fn next_stage(&mut self) {
+        if self.gate {
+            if self.sustain_point == Some(self.index) {
+                self.holding = true;
+                return;
+            }
+            if let Some((start, end)) = self.loop_points {
+                if self.index == end {
+                    self.enter_stage(start);
+                    return;
+                }
+            }
+        }
+        self.enter_stage(self.index + 1);
+    }
+}
+
+impl Default for BreakpointEnvelope {
+    fn default() -> Self {
+        Self::new(1.0, Vec::new())
+    }
+}
+
+impl Generator for BreakpointEnvelope {
+    fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        poly_sample!([self.next_level(sample_timing.sample_rate)])
+    }
+
+    fn generate_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        for poly_sample in output {
+            *poly_sample = poly_sample!([self.next_level(sample_timing.sample_rate)]);
+        }
+    }
+
+    /// Restarts from `start_level` with a closed gate.
+    fn reset(&mut self) {
+        self.gate = false;
+        self.level = self.start_level;
+        self.enter_stage(0);
+    }
+}
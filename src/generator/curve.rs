@@ -0,0 +1,29 @@
+/// Shape of an envelope segment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Curve {
+    Linear,
+    /// Changes slowly at first and speeds up towards the end of the segment.
+    Exponential,
+    /// Changes quickly at first and slows down towards the end of the segment, e.g. for natural sounding decays.
+    Logarithmic,
+    /// Adjustable curve, where `0.0` is linear, positive values behave like [`Exponential`](Self::Exponential)
+    /// and negative values like [`Logarithmic`](Self::Logarithmic).
+    Tension(f32),
+}
+
+impl Curve {
+    /// Maps the progress `t` through a segment, from `0.0` to `1.0`, to the shaped progress.
+    pub fn shape(self, t: f32) -> f32 {
+        let tension = match self {
+            Curve::Linear => return t,
+            Curve::Exponential => 5.0,
+            Curve::Logarithmic => -5.0,
+            Curve::Tension(tension) => tension,
+        };
+        if tension.abs() < 1e-3 {
+            t
+        } else {
+            ((tension * t).exp() - 1.0) / (tension.exp() - 1.0)
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use super::{Generator, Phase};
+use crate::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    /// Rising ramp.
+    Saw,
+    Square,
+    /// Random value held for each period.
+    SampleAndHold,
+}
+
+/// Low frequency oscillator for modulating parameters, without band-limiting.
+///
+/// Outputs values from `-1.0` to `1.0`, or from `0.0` to `1.0` if `unipolar` is set.
+#[derive(Clone)]
+pub struct LfoGenerator {
+    pub shape: LfoShape,
+    pub frequency: f32,
+    /// Offset of the phase from `0.0` to `1.0`, e.g. `0.25` starts a sine at its peak.
+    pub phase_offset: f32,
+    pub unipolar: bool,
+    /// Seed of the random values of [`SampleAndHold`](LfoShape::SampleAndHold).
+    pub seed: u64,
+    pub phase: Phase,
+    rng: StdRng,
+    held: Option<f32>,
+    last_phase: f32,
+}
+
+impl LfoGenerator {
+    pub fn new(shape: LfoShape, frequency: f32) -> Self {
+        Self {
+            shape,
+            frequency,
+            phase_offset: 0.0,
+            unipolar: false,
+            seed: 0,
+            phase: Phase::default(),
+            //seeded from `seed` when the first value is drawn
+            rng: StdRng::seed_from_u64(0),
+            held: None,
+            last_phase: 0.0,
+        }
+    }
+
+    /// Period of `note_value` at `bpm`, see [`sync_to_tempo`](Self::sync_to_tempo).
+    pub fn tempo_synced(shape: LfoShape, bpm: f32, note_value: f32) -> Self {
+        let mut lfo = Self::new(shape, 1.0);
+        lfo.sync_to_tempo(bpm, note_value);
+        lfo
+    }
+
+    /// Sets `frequency` so one period lasts `note_value` at `bpm`,
+    /// e.g. `0.25` for a quarter note or `1.0` for a whole bar.
+    pub fn sync_to_tempo(&mut self, bpm: f32, note_value: f32) {
+        self.frequency = bpm / 60.0 / 4.0 / note_value;
+    }
+
+    fn value(&mut self, phase: f32) -> f32 {
+        let phase = (phase + self.phase_offset).rem_euclid(1.0);
+        let value = match self.shape {
+            LfoShape::Sine => (phase * std::f32::consts::TAU).sin(),
+            LfoShape::Triangle => 1.0 - (phase * 4.0 - 1.0).abs().min((phase * 4.0 - 5.0).abs()),
+            LfoShape::Saw => 2.0 * phase - 1.0,
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::SampleAndHold => {
+                if self.held.is_none() {
+                    self.rng = StdRng::seed_from_u64(self.seed);
+                }
+                if self.held.is_none() || phase < self.last_phase {
+                    self.held = Some(self.rng.gen_range(-1.0, 1.0));
+                }
+                self.held.unwrap_or_default()
+            }
+        };
+        self.last_phase = phase;
+        if self.unipolar {
+            (value + 1.0) / 2.0
+        } else {
+            value
+        }
+    }
+}
+
+impl Default for LfoGenerator {
+    fn default() -> Self {
+        Self::new(LfoShape::Sine, 1.0)
+    }
+}
+
+impl Generator for LfoGenerator {
+    fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        let (phase, _) = self.phase.advance(sample_timing, self.frequency);
+        poly_sample!([self.value(phase)])
+    }
+
+    fn generate_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        self.phase.advance_block(sample_timing, self.frequency, output);
+        for poly_sample in output {
+            poly_sample[0] = self.value(poly_sample[0]);
+        }
+    }
+
+    /// Restarts at `phase_offset`, e.g. when a new note starts playing.
+    fn reset(&mut self) {
+        self.phase.reset();
+        self.held = None;
+    }
+
+    fn frequency(&self) -> Option<f32> {
+        Some(self.frequency)
+    }
+
+    fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+}
@@ -1,14 +1,55 @@
 mod adsr;
+mod blep;
+mod breakpoint;
+mod curve;
+mod lfo;
+mod noise;
+mod phase;
+mod pulse;
+mod sample;
+mod saw;
 mod sine;
+mod square;
 mod triangle;
 pub use adsr::AdsrGenerator;
+pub use breakpoint::{Breakpoint, BreakpointEnvelope};
+pub use curve::Curve;
+pub use lfo::{LfoGenerator, LfoShape};
+pub use noise::{NoiseColor, NoiseGenerator};
+pub use phase::{Phase, PhaseMode};
+pub use pulse::PulseGenerator;
+pub use sample::{LoopMode, SampleGenerator};
+pub use saw::SawGenerator;
 pub use sine::SineGenerator;
+pub use square::SquareGenerator;
 pub use triangle::TriangleGenerator;
 
 use crate::{PolySample, SampleTiming};
 
 pub trait Generator: Send {
     fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample;
+
+    /// Fills `output` with consecutive samples, starting at `sample_timing`.
+    fn generate_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        let mut sample_timing = *sample_timing;
+        for poly_sample in output {
+            *poly_sample = self.generate(&sample_timing);
+            sample_timing.tick();
+        }
+    }
+
+    /// Resets internal state, e.g. when a new note starts playing.
+    fn reset(&mut self) {}
+
+    /// Frequency of the generated waveform, if the generator has one.
+    fn frequency(&self) -> Option<f32> {
+        None
+    }
+
+    /// Changes the frequency of the generated waveform. Can be called every sample, e.g. for frequency modulation.
+    ///
+    /// Generators without a frequency ignore this.
+    fn set_frequency(&mut self, _frequency: f32) {}
 }
 
 impl<T: FnMut(&SampleTiming) -> PolySample + Send> Generator for T {
@@ -36,4 +77,151 @@ mod tests {
         assert_abs_diff_eq!(generator.generate(sample_timing).0[0], 0.5);
         assert_abs_diff_eq!(generator.generate(sample_timing).0[0], 0.5 * 0.5);
     }
+
+    #[test]
+    fn sample_generator() {
+        use crate::generator::{LoopMode, SampleGenerator};
+
+        let samples = (0..4).map(|i| poly_sample!([i as f32])).collect();
+        let mut generator = SampleGenerator::new(samples, 4.0);
+        let mut sample_timing = SampleTiming::new(8.0);
+        let mut play = |generator: &mut SampleGenerator, count: usize| {
+            (0..count)
+                .map(|_| {
+                    let value = generator.generate(&sample_timing)[0];
+                    sample_timing.tick();
+                    value
+                })
+                .collect::<Vec<_>>()
+        };
+
+        //resampled to twice the sample rate
+        let played = play(&mut generator, 10);
+        assert_abs_diff_eq!(played[..7], [0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0][..], epsilon = 0.1);
+        assert_abs_diff_eq!(played[8..], [0.0, 0.0][..]);
+        assert!(generator.is_finished());
+
+        generator.reset();
+        generator.loop_mode = LoopMode::PingPong;
+        generator.loop_start = 0.25;
+        generator.loop_end = 0.75;
+        generator.playback_rate = 2.0;
+        assert_abs_diff_eq!(play(&mut generator, 6)[..], [0.0, 1.0, 2.0, 3.0, 2.0, 1.0][..]);
+    }
+
+    #[test]
+    fn pulse_width() {
+        use crate::generator::PulseGenerator;
+
+        let mut sample_timing = SampleTiming::new(48000.0);
+        let mut generator = PulseGenerator::new(100.0, 0.5);
+        for pulse_width in [0.5, 0.25, 0.1].iter() {
+            generator.pulse_width = *pulse_width;
+            //average over one period
+            let mean = (0..480)
+                .map(|_| {
+                    let value = generator.generate(&sample_timing)[0];
+                    sample_timing.tick();
+                    value
+                })
+                .sum::<f32>()
+                / 480.0;
+            assert_abs_diff_eq!(mean, 2.0 * pulse_width - 1.0, epsilon = 0.01);
+        }
+    }
+
+    #[test]
+    fn frequency_modulation() {
+        use crate::generator::{SawGenerator, SineGenerator};
+
+        let mut sample_timing = SampleTiming::new(48000.0);
+        let mut sine = SineGenerator::new(440.0);
+        let mut saw = SawGenerator::new(100.0);
+        let mut last = sine.generate(&sample_timing)[0];
+        for _ in 0..48000 {
+            sample_timing.tick();
+            //vibrato and hard sync to the saw
+            let vibrato = (sample_timing.sample_clock() * 5.0 * std::f32::consts::TAU).sin();
+            sine.set_frequency(440.0 + vibrato * 100.0);
+            saw.generate(&sample_timing);
+            if saw.phase.wrapped() {
+                sine.phase.reset();
+            }
+            let value = sine.generate(&sample_timing)[0];
+            //no jumps bigger than the maximal slope of the sine, except when syncing
+            if !saw.phase.wrapped() {
+                assert!((value - last).abs() <= 540.0 / 48000.0 * std::f32::consts::TAU);
+            }
+            last = value;
+        }
+    }
+
+    #[test]
+    fn seeded_noise() {
+        use crate::generator::{NoiseColor, NoiseGenerator};
+
+        let sample_timing = SampleTiming::new(48000.0);
+        for color in [NoiseColor::White, NoiseColor::Pink, NoiseColor::Brown].iter() {
+            let mut generator = NoiseGenerator::new(*color, 42);
+            let first: Vec<f32> =
+                (0..1000).map(|_| generator.generate(&sample_timing)[0]).collect();
+            generator.reset();
+            let second: Vec<f32> =
+                (0..1000).map(|_| generator.generate(&sample_timing)[0]).collect();
+            assert_eq!(first, second);
+            assert!(first.iter().all(|value| value.abs() <= 1.0));
+            assert!(first.iter().any(|value| *value != first[0]));
+
+            //stays in range over long runs
+            let mut sample_timing = SampleTiming::new(48000.0);
+            let mut block = vec![poly_sample!(); 4800];
+            for _ in 0..200 {
+                generator.generate_block(&sample_timing, &mut block);
+                assert!(block.iter().all(|poly_sample| poly_sample[0].abs() <= 1.0));
+                sample_timing.clock += block.len();
+            }
+        }
+    }
+
+    #[test]
+    fn breakpoint_envelope() {
+        use crate::generator::{Breakpoint, BreakpointEnvelope, Curve};
+
+        let mut sample_timing = SampleTiming::new(10.0);
+        let mut envelope = BreakpointEnvelope::new(
+            0.0,
+            vec![
+                Breakpoint::new(0.2, 1.0, Curve::Linear),
+                Breakpoint::new(0.2, 0.5, Curve::Linear),
+                Breakpoint::new(0.2, 1.0, Curve::Linear),
+                Breakpoint::new(0.2, 0.0, Curve::Linear),
+            ],
+        );
+        envelope.loop_points = Some((1, 2));
+        let mut play = |envelope: &mut BreakpointEnvelope, count: usize| {
+            (0..count)
+                .map(|_| {
+                    let value = envelope.generate(&sample_timing)[0];
+                    sample_timing.tick();
+                    value
+                })
+                .collect::<Vec<_>>()
+        };
+
+        //loops between 1.0 and 0.5 while the gate is open
+        envelope.gate_on();
+        let expected = [0.5, 1.0, 0.75, 0.5, 0.75, 1.0, 0.75, 0.5, 0.75];
+        assert_abs_diff_eq!(play(&mut envelope, 9)[..], expected[..], epsilon = 0.0001);
+        envelope.gate_off();
+        assert_abs_diff_eq!(play(&mut envelope, 3)[..], [0.375, 0.0, 0.0][..], epsilon = 0.0001);
+        assert!(envelope.is_finished());
+
+        //curves keep their end points
+        for curve in [Curve::Exponential, Curve::Logarithmic, Curve::Tension(2.0)].iter() {
+            assert_abs_diff_eq!(curve.shape(0.0), 0.0);
+            assert_abs_diff_eq!(curve.shape(1.0), 1.0, epsilon = 0.0001);
+        }
+        assert!(Curve::Exponential.shape(0.5) < 0.5);
+        assert!(Curve::Logarithmic.shape(0.5) > 0.5);
+    }
 }
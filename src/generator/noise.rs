@@ -0,0 +1,97 @@
+use super::Generator;
+use crate::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoiseColor {
+    /// Equal power at all frequencies.
+    White,
+    /// Power falls by 3 dB per octave.
+    Pink,
+    /// Power falls by 6 dB per octave, also known as red noise.
+    Brown,
+}
+
+/// Generates noise, which is reproducible by using the same `seed`.
+#[derive(Clone)]
+pub struct NoiseGenerator {
+    pub color: NoiseColor,
+    pub seed: u64,
+    rng: StdRng,
+    pink_state: [f32; 7],
+    brown_state: f32,
+}
+
+impl NoiseGenerator {
+    pub fn new(color: NoiseColor, seed: u64) -> Self {
+        Self {
+            color,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            pink_state: [0.0; 7],
+            brown_state: 0.0,
+        }
+    }
+
+    fn pink(&mut self, white: f32) -> f32 {
+        //Paul Kellet's filter
+        let b = &mut self.pink_state;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.153852;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+        //the filter isn't bounded for long runs of similar values
+        (pink * 0.11).clamp(-1.0, 1.0)
+    }
+
+    fn brown(&mut self, white: f32) -> f32 {
+        //leaky integration of white noise
+        self.brown_state = (self.brown_state + 0.02 * white) / 1.02;
+        (self.brown_state * 3.5).clamp(-1.0, 1.0)
+    }
+}
+
+impl Default for NoiseGenerator {
+    fn default() -> Self {
+        Self::new(NoiseColor::White, 0)
+    }
+}
+
+impl Generator for NoiseGenerator {
+    fn generate(&mut self, _sample_timing: &SampleTiming) -> PolySample {
+        let white = self.rng.gen_range(-1.0, 1.0);
+        let value = match self.color {
+            NoiseColor::White => white,
+            NoiseColor::Pink => self.pink(white),
+            NoiseColor::Brown => self.brown(white),
+        };
+        poly_sample!([value])
+    }
+
+    fn generate_block(&mut self, _sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        for poly_sample in output.iter_mut() {
+            *poly_sample = poly_sample!([self.rng.gen_range(-1.0, 1.0)]);
+        }
+        match self.color {
+            NoiseColor::White => {}
+            NoiseColor::Pink => {
+                for poly_sample in output {
+                    poly_sample[0] = self.pink(poly_sample[0]);
+                }
+            }
+            NoiseColor::Brown => {
+                for poly_sample in output {
+                    poly_sample[0] = self.brown(poly_sample[0]);
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.color, self.seed);
+    }
+}
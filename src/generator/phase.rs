@@ -0,0 +1,108 @@
+use crate::{poly_sample, PolySample, SampleTiming};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PhaseMode {
+    /// Accumulates the phase every sample, so frequency changes don't cause phase jumps.
+    Accumulated,
+    /// Derives the phase from the absolute time of the [`SampleTiming`].
+    Absolute,
+}
+
+/// Phase of an oscillator, in the range `[0, 1)`.
+#[derive(Clone, Debug)]
+pub struct Phase {
+    pub mode: PhaseMode,
+    value: f32,
+    wrapped: bool,
+}
+
+impl Phase {
+    pub fn new(mode: PhaseMode) -> Self {
+        Self {
+            mode,
+            value: 0.0,
+            wrapped: false,
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Returns `true` if the phase completed a period with the last call of [`advance`](Self::advance).
+    ///
+    /// Useful for hard syncing another oscillator to this one.
+    pub fn wrapped(&self) -> bool {
+        self.wrapped
+    }
+
+    /// Jumps to `phase`. Only has an effect in [`PhaseMode::Accumulated`].
+    pub fn sync(&mut self, phase: f32) {
+        self.value = phase.rem_euclid(1.0);
+    }
+
+    pub fn reset(&mut self) {
+        self.sync(0.0);
+    }
+
+    /// Returns the phase of the current sample and the phase increment per sample,
+    /// then advances by one sample with `frequency`.
+    pub fn advance(&mut self, sample_timing: &SampleTiming, frequency: f32) -> (f32, f32) {
+        let phase_increment = frequency / sample_timing.sample_rate;
+        match self.mode {
+            PhaseMode::Accumulated => {
+                let phase = self.value;
+                let next = phase + phase_increment;
+                self.wrapped = !(0.0..1.0).contains(&next);
+                self.value = next.rem_euclid(1.0);
+                (phase, phase_increment)
+            }
+            PhaseMode::Absolute => {
+                let phase =
+                    (sample_timing.sample_clock_with_frequency(frequency) * frequency).fract();
+                self.wrapped = phase < self.value;
+                self.value = phase;
+                (phase, phase_increment)
+            }
+        }
+    }
+
+    /// Like [`advance`](Self::advance) for consecutive samples with the same `frequency`,
+    /// writing the phase of each sample into the first channel of `phases`.
+    ///
+    /// Returns the phase increment per sample.
+    pub fn advance_block(
+        &mut self,
+        sample_timing: &SampleTiming,
+        frequency: f32,
+        phases: &mut [PolySample],
+    ) -> f32 {
+        let phase_increment = frequency / sample_timing.sample_rate;
+        match self.mode {
+            PhaseMode::Accumulated => {
+                let mut value = self.value;
+                for phase in phases.iter_mut() {
+                    *phase = poly_sample!([value]);
+                    let next = value + phase_increment;
+                    self.wrapped = !(0.0..1.0).contains(&next);
+                    value = next.rem_euclid(1.0);
+                }
+                self.value = value;
+            }
+            PhaseMode::Absolute => {
+                let mut sample_timing = *sample_timing;
+                for phase in phases.iter_mut() {
+                    *phase = poly_sample!([self.advance(&sample_timing, frequency).0]);
+                    sample_timing.tick();
+                }
+            }
+        }
+        phase_increment
+    }
+}
+
+impl Default for Phase {
+    fn default() -> Self {
+        Self::new(PhaseMode::Accumulated)
+    }
+}
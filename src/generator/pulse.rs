@@ -0,0 +1,66 @@
+use super::{blep::poly_blep, Generator, Phase};
+use crate::prelude::*;
+
+/// Band-limited pulse wave with variable width.
+#[derive(Clone)]
+pub struct PulseGenerator {
+    pub frequency: f32,
+    /// Fraction of each period the wave is high, from `0.0` to `1.0`.
+    ///
+    /// Can be changed every sample for pulse-width modulation.
+    pub pulse_width: f32,
+    pub phase: Phase,
+}
+
+impl PulseGenerator {
+    pub fn new(frequency: f32, pulse_width: f32) -> Self {
+        Self {
+            frequency,
+            pulse_width,
+            phase: Phase::default(),
+        }
+    }
+}
+
+impl Default for PulseGenerator {
+    fn default() -> Self {
+        Self::new(440.0, 0.5)
+    }
+}
+
+impl Generator for PulseGenerator {
+    fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        let (phase, phase_increment) = self.phase.advance(sample_timing, self.frequency);
+        let phase_increment = phase_increment.abs().min(0.5);
+        //keep both edges at least one sample apart
+        let pulse_width = self.pulse_width.max(phase_increment).min(1.0 - phase_increment);
+        let mut value = if phase < pulse_width { 1.0 } else { -1.0 };
+        value += poly_blep(phase, phase_increment);
+        value -= poly_blep((phase + 1.0 - pulse_width).fract(), phase_increment);
+        poly_sample!([value])
+    }
+
+    fn generate_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        let phase_increment = self.phase.advance_block(sample_timing, self.frequency, output);
+        let phase_increment = phase_increment.abs().min(0.5);
+        let pulse_width = self.pulse_width.max(phase_increment).min(1.0 - phase_increment);
+        for poly_sample in output {
+            let phase = poly_sample[0];
+            let value = if phase < pulse_width { 1.0 } else { -1.0 };
+            poly_sample[0] = value + poly_blep(phase, phase_increment)
+                - poly_blep((phase + 1.0 - pulse_width).fract(), phase_increment);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase.reset();
+    }
+
+    fn frequency(&self) -> Option<f32> {
+        Some(self.frequency)
+    }
+
+    fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+}
@@ -0,0 +1,183 @@
+use super::Generator;
+use crate::{prelude::*, wav::read_wav};
+use anyhow::Result;
+use std::{path::Path, sync::Arc};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoopMode {
+    /// Plays the sample once and outputs silence afterwards.
+    Off,
+    /// Jumps back to `loop_start` when reaching `loop_end`.
+    Forward,
+    /// Alternates playing forwards and backwards between `loop_start` and `loop_end`.
+    PingPong,
+}
+
+/// Plays back audio samples, e.g. loaded from a WAV file.
+///
+/// Samples are resampled to the sample rate of the [`SampleTiming`] they are generated with.
+#[derive(Clone)]
+pub struct SampleGenerator {
+    samples: Arc<Vec<PolySample>>,
+    /// Sample rate of the loaded samples.
+    pub sample_rate: f32,
+    /// Time (in seconds) playback starts at.
+    pub start_offset: f32,
+    pub loop_mode: LoopMode,
+    /// Start of the loop (in seconds).
+    pub loop_start: f32,
+    /// End of the loop (in seconds).
+    pub loop_end: f32,
+    /// Playback speed, where `2.0` plays twice as fast and one octave higher.
+    pub playback_rate: f32,
+    position: Option<f64>,
+    reverse: bool,
+}
+
+impl SampleGenerator {
+    pub fn new(samples: Vec<PolySample>, sample_rate: f32) -> Self {
+        let duration = samples.len() as f32 / sample_rate;
+        Self {
+            samples: Arc::new(samples),
+            sample_rate,
+            start_offset: 0.0,
+            loop_mode: LoopMode::Off,
+            loop_start: 0.0,
+            loop_end: duration,
+            playback_rate: 1.0,
+            position: None,
+            reverse: false,
+        }
+    }
+
+    /// Loads all samples of a PCM or float WAV file into memory.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let (samples, sample_rate) = read_wav(path)?;
+        Ok(Self::new(samples, sample_rate))
+    }
+
+    pub fn samples(&self) -> &[PolySample] {
+        &self.samples
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate
+    }
+
+    /// Sets [`playback_rate`](Self::playback_rate) to transpose the sample by `semitones`.
+    pub fn set_pitch(&mut self, semitones: f32) {
+        self.playback_rate = 2.0f32.powf(semitones / 12.0);
+    }
+
+    /// Returns `true` when playback reached the end of a non-looping sample.
+    pub fn is_finished(&self) -> bool {
+        match self.position {
+            Some(position) => {
+                self.loop_mode == LoopMode::Off && position >= self.samples.len() as f64
+            }
+            None => false,
+        }
+    }
+
+    /// Cubic hermite interpolation between the frames around `position`.
+    fn interpolate(&self, position: f64) -> PolySample {
+        let index = position.floor() as isize;
+        let t = (position - index as f64) as f32;
+        let last = self.samples.len() as isize - 1;
+        let frame = |i: isize| &self.samples[(index + i).max(0).min(last) as usize];
+
+        let (y0, y1, y2, y3) = (frame(-1), frame(0), frame(1), frame(2));
+        PolySample(
+            (0..y1.len())
+                .map(|channel| {
+                    let (y0, y1, y2, y3) = (y0[channel], y1[channel], y2[channel], y3[channel]);
+                    let c1 = 0.5 * (y2 - y0);
+                    let c2 = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+                    let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+                    ((c3 * t + c2) * t + c1) * t + y1
+                })
+                .collect(),
+        )
+    }
+
+    fn advance(&mut self, position: f64, step: f64) -> f64 {
+        let loop_start = (self.loop_start * self.sample_rate) as f64;
+        let loop_end = (self.loop_end * self.sample_rate) as f64;
+        let loop_length = loop_end - loop_start;
+
+        match self.loop_mode {
+            LoopMode::Forward if loop_length > 0.0 => {
+                let mut position = position + step;
+                if position >= loop_end {
+                    position = loop_start + (position - loop_start) % loop_length;
+                }
+                position
+            }
+            LoopMode::PingPong if loop_length > 0.0 => {
+                let mut position = if self.reverse { position - step } else { position + step };
+                //reflect at the loop points, until inside the loop
+                loop {
+                    if !self.reverse && position >= loop_end {
+                        position = 2.0 * loop_end - position;
+                        self.reverse = true;
+                    } else if self.reverse && position <= loop_start {
+                        position = 2.0 * loop_start - position;
+                        self.reverse = false;
+                    } else {
+                        break position;
+                    }
+                }
+            }
+            _ => position + step,
+        }
+    }
+}
+
+impl Default for SampleGenerator {
+    fn default() -> Self {
+        Self::new(Vec::new(), 44100.0)
+    }
+}
+
+impl Generator for SampleGenerator {
+    fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        let start_offset = (self.start_offset * self.sample_rate) as f64;
+        let position = *self.position.get_or_insert(start_offset);
+
+        let poly_sample = if self.samples.is_empty() {
+            poly_sample!([0.0])
+        } else if self.is_finished() {
+            &self.samples[0] * 0.0
+        } else {
+            self.interpolate(position)
+        };
+
+        let step = (self.playback_rate * self.sample_rate / sample_timing.sample_rate) as f64;
+        self.position = Some(self.advance(position, step));
+
+        poly_sample
+    }
+
+    fn generate_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        let start_offset = (self.start_offset * self.sample_rate) as f64;
+        let mut position = *self.position.get_or_insert(start_offset);
+        let step = (self.playback_rate * self.sample_rate / sample_timing.sample_rate) as f64;
+        let len = self.samples.len() as f64;
+        for poly_sample in output.iter_mut() {
+            *poly_sample = if self.samples.is_empty() {
+                poly_sample!([0.0])
+            } else if self.loop_mode == LoopMode::Off && position >= len {
+                &self.samples[0] * 0.0
+            } else {
+                self.interpolate(position)
+            };
+            position = self.advance(position, step);
+        }
+        self.position = Some(position);
+    }
+
+    fn reset(&mut self) {
+        self.position = None;
+        self.reverse = false;
+    }
+}
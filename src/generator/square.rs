@@ -0,0 +1,58 @@
+use super::{blep::poly_blep, Generator, Phase};
+use crate::prelude::*;
+
+/// Band-limited square wave.
+#[derive(Clone)]
+pub struct SquareGenerator {
+    pub frequency: f32,
+    pub phase: Phase,
+}
+
+impl SquareGenerator {
+    pub fn new(frequency: f32) -> Self {
+        Self {
+            frequency,
+            phase: Phase::default(),
+        }
+    }
+}
+
+impl Default for SquareGenerator {
+    fn default() -> Self {
+        Self::new(440.0)
+    }
+}
+
+impl Generator for SquareGenerator {
+    fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        let (phase, phase_increment) = self.phase.advance(sample_timing, self.frequency);
+        let phase_increment = phase_increment.abs().min(0.5);
+        let mut value = if phase < 0.5 { 1.0 } else { -1.0 };
+        value += poly_blep(phase, phase_increment);
+        value -= poly_blep((phase + 0.5).fract(), phase_increment);
+        poly_sample!([value])
+    }
+
+    fn generate_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        let phase_increment = self.phase.advance_block(sample_timing, self.frequency, output);
+        let phase_increment = phase_increment.abs().min(0.5);
+        for poly_sample in output {
+            let phase = poly_sample[0];
+            let value = if phase < 0.5 { 1.0 } else { -1.0 };
+            poly_sample[0] = value + poly_blep(phase, phase_increment)
+                - poly_blep((phase + 0.5).fract(), phase_increment);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase.reset();
+    }
+
+    fn frequency(&self) -> Option<f32> {
+        Some(self.frequency)
+    }
+
+    fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
+    }
+}
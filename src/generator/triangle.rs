@@ -1,15 +1,17 @@
-use super::Generator;
+use super::{Generator, Phase};
 use crate::prelude::*;
 
 #[derive(Clone)]
 pub struct TriangleGenerator {
     pub frequency: f32,
+    pub phase: Phase,
 }
 
 impl TriangleGenerator {
     pub fn new(frequency: f32) -> Self {
         Self {
             frequency,
+            phase: Phase::default(),
         }
     }
 }
@@ -22,7 +24,26 @@ impl Default for TriangleGenerator {
 
 impl Generator for TriangleGenerator {
     fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample {
-        let sample_clock = sample_timing.sample_clock_with_frequency(self.frequency);
-        poly_sample!([(((sample_clock * self.frequency * 4.0) % 4.0) - 2.0).abs() - 1.0])
+        let (phase, _) = self.phase.advance(sample_timing, self.frequency);
+        poly_sample!([(phase * 4.0 - 2.0).abs() - 1.0])
+    }
+
+    fn generate_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        self.phase.advance_block(sample_timing, self.frequency, output);
+        for poly_sample in output {
+            poly_sample[0] = (poly_sample[0] * 4.0 - 2.0).abs() - 1.0;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase.reset();
+    }
+
+    fn frequency(&self) -> Option<f32> {
+        Some(self.frequency)
+    }
+
+    fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
     }
 }
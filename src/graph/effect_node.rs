@@ -0,0 +1,40 @@
+use super::{Node, Port};
+use crate::{effect::Effect, prelude::*};
+use std::any::Any;
+
+/// Processes the audio input `"in"` by an [`Effect`] into the audio output `"out"`.
+pub struct EffectNode<E: Effect> {
+    pub effect: E,
+}
+
+impl<E: Effect> EffectNode<E> {
+    pub fn new(effect: E) -> Self {
+        Self {
+            effect,
+        }
+    }
+}
+
+impl<E: 'static + Effect> Node for EffectNode<E> {
+    fn inputs(&self) -> Vec<Port> {
+        vec![Port::audio("in")]
+    }
+
+    fn outputs(&self) -> Vec<Port> {
+        vec![Port::audio("out")]
+    }
+
+    fn process_block(
+        &mut self,
+        sample_timing: &SampleTiming,
+        inputs: &[Vec<PolySample>],
+        outputs: &mut [Vec<PolySample>],
+    ) {
+        outputs[0].clone_from_slice(&inputs[0]);
+        self.effect.process_block(sample_timing, &mut outputs[0]);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
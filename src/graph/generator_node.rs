@@ -0,0 +1,76 @@
+use super::{Node, Port, PortKind};
+use crate::{generator::Generator, prelude::*};
+use std::any::Any;
+
+/// Plays a [`Generator`] into the audio output `"out"`.
+///
+/// Generators with a frequency get a control input `"frequency"`, which is added to [`frequency`](Self::frequency).
+pub struct GeneratorNode<G: Generator> {
+    pub generator: G,
+    /// Base frequency the `"frequency"` input is added to.
+    pub frequency: Option<f32>,
+    output_kind: PortKind,
+}
+
+impl<G: Generator> GeneratorNode<G> {
+    pub fn new(generator: G) -> Self {
+        Self {
+            frequency: generator.frequency(),
+            generator,
+            output_kind: PortKind::Audio,
+        }
+    }
+
+    /// Makes `"out"` a control output, e.g. for modulating the frequency of another generator.
+    pub fn control(generator: G) -> Self {
+        Self {
+            output_kind: PortKind::Control,
+            ..Self::new(generator)
+        }
+    }
+}
+
+impl<G: 'static + Generator> Node for GeneratorNode<G> {
+    fn inputs(&self) -> Vec<Port> {
+        match self.frequency {
+            Some(_) => vec![Port::control("frequency")],
+            None => Vec::new(),
+        }
+    }
+
+    fn outputs(&self) -> Vec<Port> {
+        vec![Port {
+            name: "out",
+            kind: self.output_kind,
+        }]
+    }
+
+    fn process_block(
+        &mut self,
+        sample_timing: &SampleTiming,
+        inputs: &[Vec<PolySample>],
+        outputs: &mut [Vec<PolySample>],
+    ) {
+        let output = &mut outputs[0];
+        let (frequency, modulation) = match (self.frequency, inputs.first()) {
+            (Some(frequency), Some(modulation)) => (frequency, modulation),
+            _ => return self.generator.generate_block(sample_timing, output),
+        };
+
+        if modulation.iter().all(|poly_sample| poly_sample[0] == 0.0) {
+            self.generator.set_frequency(frequency);
+            self.generator.generate_block(sample_timing, output);
+        } else {
+            let mut sample_timing = *sample_timing;
+            for (poly_sample, modulation) in output.iter_mut().zip(modulation) {
+                self.generator.set_frequency(frequency + modulation[0]);
+                *poly_sample = self.generator.generate(&sample_timing);
+                sample_timing.tick();
+            }
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,405 @@
+mod effect_node;
+mod generator_node;
+mod patch_node;
+
+use crate::prelude::*;
+pub use effect_node::EffectNode;
+pub use generator_node::GeneratorNode;
+pub use patch_node::PatchNode;
+use std::{any::Any, fmt};
+
+/// What a [`Port`] carries. Only ports of the same kind can be connected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortKind {
+    Audio,
+    /// Parameter values, e.g. a frequency, in the first channel.
+    Control,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Port {
+    pub name: &'static str,
+    pub kind: PortKind,
+}
+
+impl Port {
+    pub fn audio(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: PortKind::Audio,
+        }
+    }
+
+    pub fn control(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: PortKind::Control,
+        }
+    }
+}
+
+/// Component of a [`Graph`], processing blocks of its input ports into its output ports.
+pub trait Node: Send {
+    /// Input ports, queried once when the node is added to a graph.
+    fn inputs(&self) -> Vec<Port>;
+
+    /// Output ports, queried once when the node is added to a graph.
+    fn outputs(&self) -> Vec<Port>;
+
+    /// Processes one block, starting at `sample_timing`.
+    ///
+    /// `inputs` and `outputs` are ordered like the ports, all buffers have the length of the block.
+    /// Unconnected inputs are silent, inputs with multiple connections are summed.
+    fn process_block(
+        &mut self,
+        sample_timing: &SampleTiming,
+        inputs: &[Vec<PolySample>],
+        outputs: &mut [Vec<PolySample>],
+    );
+
+    /// Returns `self`, so nodes from [`Graph::node_mut`] can be downcast to change them in place.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Handle of a node in a [`Graph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GraphError {
+    UnknownNode(NodeId),
+    UnknownPort(String),
+    /// Tried to connect ports of different [`PortKind`]s.
+    PortKindMismatch(PortKind, PortKind),
+    /// The connection would create a cycle, which is only allowed with [`Graph::connect_feedback`].
+    Cycle,
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::UnknownNode(id) => write!(f, "unknown node {:?}", id),
+            GraphError::UnknownPort(name) => write!(f, "unknown port \"{}\"", name),
+            GraphError::PortKindMismatch(from, to) => {
+                write!(f, "can't connect {:?} port to {:?} port", from, to)
+            }
+            GraphError::Cycle => write!(f, "connection would create a cycle"),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+struct GraphNode {
+    node: Box<dyn Node>,
+    inputs: Vec<Port>,
+    outputs: Vec<Port>,
+    input_buffers: Vec<Vec<PolySample>>,
+    output_buffers: Vec<Vec<PolySample>>,
+}
+
+struct Connection {
+    from: (NodeId, usize),
+    to: (NodeId, usize),
+    /// Output of the previous block for feedback connections.
+    feedback: Option<Vec<PolySample>>,
+}
+
+/// Connects nodes by their ports and evaluates them in topological order.
+///
+/// Outputs can be connected to multiple inputs, multiple connections to one input are summed.
+/// Cycles are only allowed through feedback connections, which delay the signal by one block.
+/// The graph is played as a patch by setting its [`output`](Self::set_output).
+#[derive(Default)]
+pub struct Graph {
+    nodes: Vec<(NodeId, GraphNode)>,
+    connections: Vec<Connection>,
+    output: Option<(NodeId, usize)>,
+    //indices of `nodes` in evaluation order, recalculated after changes
+    order: Option<Vec<usize>>,
+    next_id: usize,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node<N: 'static + Node>(&mut self, node: N) -> NodeId {
+        self.next_id += 1;
+        let id = NodeId(self.next_id);
+        let inputs = node.inputs();
+        let outputs = node.outputs();
+        self.nodes.push((
+            id,
+            GraphNode {
+                node: Box::new(node),
+                input_buffers: vec![Vec::new(); inputs.len()],
+                output_buffers: vec![Vec::new(); outputs.len()],
+                inputs,
+                outputs,
+            },
+        ));
+        self.order = None;
+        id
+    }
+
+    /// Removes a node and all of its connections.
+    pub fn remove_node(&mut self, id: NodeId) -> Option<Box<dyn Node>> {
+        let index = self.index(id)?;
+        self.connections.retain(|connection| connection.from.0 != id && connection.to.0 != id);
+        if self.output.is_some_and(|(output, _)| output == id) {
+            self.output = None;
+        }
+        self.order = None;
+        Some(self.nodes.remove(index).1.node)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut (dyn Node + 'static)> {
+        let index = self.index(id)?;
+        Some(self.nodes[index].1.node.as_mut())
+    }
+
+    /// Like [`node_mut`](Self::node_mut), downcast to the type of the node, e.g. `EffectNode<Biquad>`.
+    ///
+    /// Returns `None` if the node doesn't exist or is of another type.
+    pub fn node_mut_as<N: 'static + Node>(&mut self, id: NodeId) -> Option<&mut N> {
+        self.node_mut(id)?.as_any_mut().downcast_mut()
+    }
+
+    pub fn node_ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.iter().map(|(id, _)| *id)
+    }
+
+    /// Connects the output port `from_port` of `from` to the input port `to_port` of `to`.
+    pub fn connect(
+        &mut self,
+        from: NodeId,
+        from_port: &str,
+        to: NodeId,
+        to_port: &str,
+    ) -> Result<(), GraphError> {
+        self.add_connection(from, from_port, to, to_port, false)
+    }
+
+    /// Like [`connect`](Self::connect), but `to` receives the output of the previous block, so cycles are allowed.
+    pub fn connect_feedback(
+        &mut self,
+        from: NodeId,
+        from_port: &str,
+        to: NodeId,
+        to_port: &str,
+    ) -> Result<(), GraphError> {
+        self.add_connection(from, from_port, to, to_port, true)
+    }
+
+    /// Removes the connection between the ports, returns `false` if there was none.
+    pub fn disconnect(&mut self, from: NodeId, from_port: &str, to: NodeId, to_port: &str) -> bool {
+        let (from_port, to_port) =
+            match (self.output_port(from, from_port), self.input_port(to, to_port)) {
+                (Ok(from_port), Ok(to_port)) => (from_port, to_port),
+                _ => return false,
+            };
+        let count = self.connections.len();
+        self.connections.retain(|connection| {
+            connection.from != (from, from_port) || connection.to != (to, to_port)
+        });
+        self.order = None;
+        self.connections.len() != count
+    }
+
+    /// Sets the audio output port the graph is played from as a [`Patch`].
+    pub fn set_output(&mut self, node: NodeId, port: &str) -> Result<(), GraphError> {
+        let port_index = self.output_port(node, port)?;
+        self.output = Some((node, port_index));
+        Ok(())
+    }
+
+    /// Evaluates all nodes for one block, starting at `sample_timing`.
+    pub fn process_block(&mut self, sample_timing: &SampleTiming, len: usize) {
+        if self.order.is_none() {
+            self.order = Some(self.topological_order());
+        }
+        let order = self.order.take().unwrap_or_default();
+
+        for &index in &order {
+            let id = self.nodes[index].0;
+            let mut input_buffers = std::mem::take(&mut self.nodes[index].1.input_buffers);
+            for input in &mut input_buffers {
+                input.clear();
+                input.resize(len, poly_sample!());
+            }
+
+            for connection in self.connections.iter().filter(|connection| connection.to.0 == id) {
+                let source = match &connection.feedback {
+                    Some(previous) => &previous[..],
+                    None => match self.nodes.iter().find(|(id, _)| *id == connection.from.0) {
+                        Some((_, node)) => &node.output_buffers[connection.from.1][..],
+                        None => continue,
+                    },
+                };
+                for (input, poly_sample) in input_buffers[connection.to.1].iter_mut().zip(source) {
+                    input.mix(poly_sample);
+                }
+            }
+            for poly_sample in input_buffers.iter_mut().flatten() {
+                if poly_sample.is_empty() {
+                    poly_sample.push(0.0);
+                }
+            }
+
+            let node = &mut self.nodes[index].1;
+            for output in &mut node.output_buffers {
+                output.clear();
+                output.resize(len, poly_sample!([0.0]));
+            }
+            node.node.process_block(sample_timing, &input_buffers, &mut node.output_buffers);
+            node.input_buffers = input_buffers;
+        }
+
+        //feedback connections read this block during the next one
+        let nodes = &self.nodes;
+        for connection in &mut self.connections {
+            let (from, from_port) = connection.from;
+            if let Some(previous) = &mut connection.feedback {
+                previous.clear();
+                if let Some((_, node)) = nodes.iter().find(|(id, _)| *id == from) {
+                    previous.extend_from_slice(&node.output_buffers[from_port]);
+                }
+            }
+        }
+        self.order = Some(order);
+    }
+
+    /// Output of the last processed block at the output port `port` of `node`.
+    pub fn output_buffer(&self, node: NodeId, port: &str) -> Result<&[PolySample], GraphError> {
+        let port_index = self.output_port(node, port)?;
+        let index = self.index(node).ok_or(GraphError::UnknownNode(node))?;
+        Ok(&self.nodes[index].1.output_buffers[port_index])
+    }
+
+    fn add_connection(
+        &mut self,
+        from: NodeId,
+        from_port: &str,
+        to: NodeId,
+        to_port: &str,
+        feedback: bool,
+    ) -> Result<(), GraphError> {
+        let from_port = self.output_port(from, from_port)?;
+        let to_port = self.input_port(to, to_port)?;
+        let from_kind = self.nodes[self.index(from).unwrap()].1.outputs[from_port].kind;
+        let to_kind = self.nodes[self.index(to).unwrap()].1.inputs[to_port].kind;
+        if from_kind != to_kind {
+            return Err(GraphError::PortKindMismatch(from_kind, to_kind));
+        }
+        if !feedback && (from == to || self.reaches(to, from)) {
+            return Err(GraphError::Cycle);
+        }
+        self.connections.push(Connection {
+            from: (from, from_port),
+            to: (to, to_port),
+            feedback: if feedback { Some(Vec::new()) } else { None },
+        });
+        self.order = None;
+        Ok(())
+    }
+
+    fn index(&self, id: NodeId) -> Option<usize> {
+        self.nodes.iter().position(|(node_id, _)| *node_id == id)
+    }
+
+    fn input_port(&self, id: NodeId, name: &str) -> Result<usize, GraphError> {
+        let index = self.index(id).ok_or(GraphError::UnknownNode(id))?;
+        let inputs = &self.nodes[index].1.inputs;
+        inputs
+            .iter()
+            .position(|port| port.name == name)
+            .ok_or_else(|| GraphError::UnknownPort(name.to_owned()))
+    }
+
+    fn output_port(&self, id: NodeId, name: &str) -> Result<usize, GraphError> {
+        let index = self.index(id).ok_or(GraphError::UnknownNode(id))?;
+        let outputs = &self.nodes[index].1.outputs;
+        outputs
+            .iter()
+            .position(|port| port.name == name)
+            .ok_or_else(|| GraphError::UnknownPort(name.to_owned()))
+    }
+
+    /// Returns `true` if `to` can be reached from `from` without feedback connections.
+    fn reaches(&self, from: NodeId, to: NodeId) -> bool {
+        let mut stack = vec![from];
+        let mut visited = Vec::new();
+        while let Some(id) = stack.pop() {
+            if id == to {
+                return true;
+            }
+            if visited.contains(&id) {
+                continue;
+            }
+            visited.push(id);
+            stack.extend(
+                self.connections
+                    .iter()
+                    .filter(|connection| connection.feedback.is_none() && connection.from.0 == id)
+                    .map(|connection| connection.to.0),
+            );
+        }
+        false
+    }
+
+    fn topological_order(&self) -> Vec<usize> {
+        //Kahn's algorithm, keeping the insertion order of independent nodes
+        let mut in_degrees: Vec<usize> = self
+            .nodes
+            .iter()
+            .map(|(id, _)| {
+                self.connections
+                    .iter()
+                    .filter(|connection| connection.feedback.is_none() && connection.to.0 == *id)
+                    .count()
+            })
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(index) =
+            (0..self.nodes.len()).find(|index| in_degrees[*index] == 0 && !order.contains(index))
+        {
+            order.push(index);
+            let id = self.nodes[index].0;
+            for connection in &self.connections {
+                if connection.feedback.is_none() && connection.from.0 == id {
+                    if let Some(to) = self.index(connection.to.0) {
+                        in_degrees[to] -= 1;
+                    }
+                }
+            }
+        }
+        order
+    }
+}
+
+impl Patch for Graph {
+    fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        let mut output = [poly_sample!()];
+        self.next_block(sample_timing, &mut output);
+        let [poly_sample] = output;
+        poly_sample
+    }
+
+    fn next_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) -> usize {
+        self.process_block(sample_timing, output.len());
+        let source = self
+            .output
+            .and_then(|(id, port)| Some(&self.nodes[self.index(id)?].1.output_buffers[port]));
+        for (i, poly_sample) in output.iter_mut().enumerate() {
+            *poly_sample = match source.and_then(|source| source.get(i)) {
+                Some(source) => source.clone(),
+                None => poly_sample!([0.0]),
+            };
+        }
+        output.len()
+    }
+}
+
+impl OutPatch for Graph {}
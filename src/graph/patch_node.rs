@@ -0,0 +1,50 @@
+use super::{Node, Port};
+use crate::prelude::*;
+use std::any::Any;
+
+/// Plays a [`Patch`] into the audio output `"out"`, which is silent once the patch finished.
+pub struct PatchNode<P: Patch> {
+    pub patch: P,
+}
+
+impl<P: Patch> PatchNode<P> {
+    pub fn new(patch: P) -> Self {
+        Self {
+            patch,
+        }
+    }
+}
+
+impl<P: 'static + Patch> Node for PatchNode<P> {
+    fn inputs(&self) -> Vec<Port> {
+        Vec::new()
+    }
+
+    fn outputs(&self) -> Vec<Port> {
+        vec![Port::audio("out")]
+    }
+
+    fn process_block(
+        &mut self,
+        sample_timing: &SampleTiming,
+        _inputs: &[Vec<PolySample>],
+        outputs: &mut [Vec<PolySample>],
+    ) {
+        let output = &mut outputs[0];
+        let written = if self.patch.state() == PatchState::Finished {
+            0
+        } else {
+            self.patch.next_block(sample_timing, output)
+        };
+        for poly_sample in &mut output[written..] {
+            *poly_sample = poly_sample!([0.0]);
+        }
+        for poly_sample in output.iter_mut().filter(|poly_sample| poly_sample.is_empty()) {
+            poly_sample.push(0.0);
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
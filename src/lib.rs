@@ -13,25 +13,44 @@
 //! * Generators (Generating waveforms)
 //!     * Sine
 //!     * Triangle
-//!     * ADSR (Attack-Delay-Sustain-Release)
+//!     * Saw, Square and Pulse (band-limited, with pulse-width modulation)
+//!     * ADSR (Attack-Delay-Sustain-Release, with fixed duration or gated)
+//!     * Breakpoint Envelope (Arbitrary stages with curves, sustain and loop points)
+//!     * Sample (Plays samples from WAV files, with looping and resampling)
+//!     * Noise (White, Pink, Brown)
+//!     * LFO (Sine, Triangle, Saw, Square, Sample & Hold, tempo-synced)
 //!     * **more to come**
 //!     * **easily expandable**
 //! * Audio Effects (Transforming audio)
-//!     * Delay
+//!     * Delay (interpolated, tempo-synced, ping-pong, filtered and saturated feedback)
 //!     * Lag
 //!     * Oscilloscope (Allows easy plotting of the waveform)
+//!     * Biquad Filter (Low-pass, High-pass, Band-pass, Notch, All-pass, Shelves, Peaking)
+//!     * State Variable Filter and Ladder Filter (Resonant filters for synth voices)
+//!     * Reverb (Freeverb-style, turns mono into stereo)
+//!     * Convolver (Convolution reverb with impulse responses, partitioned FFT)
+//!     * Chorus, Flanger and Phaser (internal LFO or external modulation)
+//!     * Compressor, Limiter (lookahead brickwall), Expander and NoiseGate (peak/RMS, stereo linked)
+//!     * Ducker (and sidechain input for the other dynamics effects)
+//!     * Distortion (waveshaping with oversampling)
+//!     * Bitcrusher and Downsampler (fractional bit depth and sample rate)
+//!     * Panner (pan laws and balance) and StereoWidth (mid/side)
 //!     * **more to come**
 //!     * **easily expandable**
 //! * Synthesizer
 //!     * BasicSynthesizer (Simplified API of playing sounds, **will be expanded**)
+//!     * PolySynthesizer (Plays multiple notes at once, with voice stealing)
 //!     * **more to come**
 //!     * **expandable**
 //! * Patches for combining and connecting components
+//!     * MasterPatch (Plays until all of its patches have finished)
+//!     * Mixer (Tracks with gain, pan, mute, solo, insert effects and sends to buses)
+//! * Audio graph (Generators, Effects and Patches as nodes with audio and control ports, arbitrary routing and feedback)
+//! * Block processing (Patches, Generators and Effects process whole buffers at once)
+//! * Parameter modulation (Params and modulation matrices for BasicSynthesizer, generators and effects, sources like LFOs, envelopes and MIDI CCs)
+//! * Offline rendering of patches into WAV files
 //!
 //! ### Planned Features
-//! * Audio File Support
-//!     * Sample "Generator" (which plays samples from file)
-//!     * Exporting audio
 //! * Expanded I/O support
 //!     * Improve Interface, expose more functionality of [`cpal`](https://crates.io/crates/cpal)
 //!     * VST support (to easily create VST plugins)
@@ -60,7 +79,7 @@
 //!
 //! ## Get Started
 //!
-//! ```
+//! ```no_run
 //! use dawrs::{
 //!     generator::{AdsrGenerator, SineGenerator},
 //!     prelude::*,
@@ -110,32 +129,91 @@
 mod cpal;
 pub mod effect;
 pub mod generator;
+pub mod graph;
+pub mod modulation;
 pub mod patch;
 mod poly_sample;
+pub mod render;
 mod sample_timing;
 pub mod synthesizer;
+mod wav;
 
 pub use crate::cpal::Cpal;
 pub use poly_sample::PolySample;
+pub use render::WavRenderer;
 pub use sample_timing::SampleTiming;
 
 pub mod prelude {
     pub use crate::{
-        effect::Effect, generator::Generator, patch::*, poly_sample, Cpal, PolySample, SampleTiming,
+        effect::Effect, generator::Generator, patch::*, poly_sample, Cpal, PolySample,
+        SampleTiming, WavRenderer,
     };
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        effect::{Delay, Effect, Oscilloscope},
-        generator::{AdsrGenerator, Generator, SineGenerator, TriangleGenerator},
+        cpal::CpalEvent,
+        effect::{
+            Biquad, BiquadType, Bitcrusher, Chorus, Compressor, Convolver, Delay, Distortion,
+            Downsampler, Ducker, Effect, Expander, Flanger, LadderFilter, Limiter, NoiseGate,
+            Oscilloscope, PanLaw, Panner, Phaser, Reverb, SidechainEffect, StateVariableFilter,
+            StateVariableOutput, StereoWidth, TransferCurve,
+        },
+        generator::{
+            AdsrGenerator, BreakpointEnvelope, Generator, LfoGenerator, LfoShape, LoopMode,
+            NoiseColor, NoiseGenerator, PulseGenerator, SampleGenerator, SawGenerator,
+            SineGenerator, SquareGenerator, TriangleGenerator,
+        },
+        graph::{EffectNode, GeneratorNode, Graph, GraphError, Node, Port, PortKind},
+        modulation::{ControlValue, ModSource, Modulated, Param},
         prelude::*,
-        synthesizer::BasicSynthesizer,
+        render::{render_to_buffer, BitDepth},
+        synthesizer::{BasicSynthesizer, PolySynthesizer, SynthDestination, VoiceStealing},
     };
+    use approx::assert_abs_diff_eq;
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn peak(buffer: &[PolySample], channel: usize) -> f32 {
+        buffer.iter().map(|poly_sample| poly_sample[channel].abs()).fold(0.0, f32::max)
+    }
+
+    fn rms(buffer: &[PolySample], channel: usize) -> f32 {
+        let sum: f32 = buffer.iter().map(|poly_sample| poly_sample[channel].powi(2)).sum();
+        (sum / buffer.len() as f32).sqrt()
+    }
 
-    fn midi_id_to_frequency(midi_id: u8) -> f32 {
-        (2.0f32).powf((midi_id - 69) as f32 / 12.0) * 440.0
+    fn zero_crossing_frequency(buffer: &[PolySample], channel: usize) -> f32 {
+        let crossings = buffer
+            .windows(2)
+            .filter(|window| (window[0][channel] < 0.0) != (window[1][channel] < 0.0))
+            .count();
+        crossings as f32 / 2.0 / (buffer.len() as f32 / SAMPLE_RATE)
+    }
+
+    #[test]
+    fn sine() {
+        struct MyPatch {
+            sine_gen: SineGenerator,
+        }
+
+        impl Patch for MyPatch {
+            fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+                self.sine_gen.generate(sample_timing) * 0.5
+            }
+        }
+
+        let mut patch = MyPatch {
+            sine_gen: SineGenerator::new(440.0),
+        };
+
+        let buffer = render_to_buffer(&mut patch, SAMPLE_RATE, 1.0);
+        assert_eq!(buffer.len(), SAMPLE_RATE as usize);
+        assert_abs_diff_eq!(peak(&buffer, 0), 0.5, epsilon = 0.001);
+        assert_abs_diff_eq!(rms(&buffer, 0), 0.5 / 2.0f32.sqrt(), epsilon = 0.001);
+        assert_abs_diff_eq!(zero_crossing_frequency(&buffer, 0), 440.0, epsilon = 1.0);
     }
 
     #[test]
@@ -148,6 +226,7 @@ mod tests {
             glide_length: usize,
             triangle_synth: TriangleGenerator,
             sine_cv: SineGenerator,
+            panner: Panner,
         }
 
         impl Patch for MyPatch {
@@ -158,17 +237,15 @@ mod tests {
 
                 let clock = sample_timing.clock + offset;
 
-                if clock % bar == 0 {
-                    if clock != 0 {
-                        if clock % (bar * 6) == 0 {
-                            self.original_frequency = self.triangle_synth.frequency;
-                            self.target_frequency = self.original_frequency / 3.0;
-                            self.glide_length = 24000;
-                        } else {
-                            self.original_frequency = self.triangle_synth.frequency;
-                            self.target_frequency = self.original_frequency * 1.2;
-                            self.glide_length = 24000;
-                        }
+                if clock % bar == 0 && clock != 0 {
+                    if clock % (bar * 6) == 0 {
+                        self.original_frequency = self.triangle_synth.frequency;
+                        self.target_frequency = self.original_frequency / 3.0;
+                        self.glide_length = 24000;
+                    } else {
+                        self.original_frequency = self.triangle_synth.frequency;
+                        self.target_frequency = self.original_frequency * 1.2;
+                        self.glide_length = 24000;
                     }
                 }
 
@@ -179,23 +256,16 @@ mod tests {
                     self.glide_length -= 1;
                 }
 
-                let mut lead = self.triangle_synth.generate(&sample_timing)[0];
+                let mut lead = self.triangle_synth.generate(sample_timing)[0];
                 //turn volume down
                 lead *= 0.1;
 
-                let mut cv = self.sine_cv.generate(&sample_timing);
-                //cv map from [-1,1] to [0,1]
-                cv.linear_map(-1.0..1.0, 0.0..1.0);
-                let second_channel = 1.0 - cv[0];
-                cv.push(second_channel);
-
                 //cv pans lead
-                lead * cv
+                self.panner.pan = self.sine_cv.generate(sample_timing)[0];
+                self.panner.process(sample_timing, poly_sample!([lead]))
             }
         }
 
-        let mut cpal = Cpal::new().unwrap();
-
         let mut master_patch = MasterPatch::default();
 
         let patch = MyPatch {
@@ -203,28 +273,27 @@ mod tests {
             ..MyPatch::default()
         };
 
-        let mut patch2 = patch.clone();
-        //second synth starts on B instead of A
-        patch2.triangle_synth.frequency = 493.88;
-        //offset of 1 second
-        patch2.time_offset = 1.0;
-
         master_patch.add_patch(patch);
-        //master_patch.add_patch(patch2);
 
-        cpal.play_patch(&mut master_patch);
+        let buffer = render_to_buffer(&mut master_patch, SAMPLE_RATE, 10.0);
+        assert_eq!(buffer.len(), 10 * SAMPLE_RATE as usize);
+        assert!(buffer.iter().all(|poly_sample| poly_sample.len() == 2));
+        //each lead is turned down to 0.1 and panned at constant power
+        assert!(peak(&buffer, 0) <= 0.2);
+        assert!(peak(&buffer, 1) <= 0.4);
+        assert!(rms(&buffer, 1) > 0.01);
     }
 
     #[test]
     fn mary_had_a_little_lamb_chordified() {
         #[derive(Default, Clone)]
         struct MyPatch {
-            synth: BasicSynthesizer<TriangleGenerator>,
-            delay: Delay,
+            synth: PolySynthesizer<TriangleGenerator>,
             melody: Vec<u8>,
             note_lengths: Vec<u8>,
             melody_index: usize,
             current_note_quarter_count: u8,
+            release_clock: usize,
         }
 
         impl Patch for MyPatch {
@@ -248,21 +317,23 @@ mod tests {
                     if self.current_note_quarter_count == 0 {
                         let note_length = self.note_lengths[self.melody_index];
                         let note_length = note_length as f32;
-                        self.synth.play(quarter_duration * note_length as f32 - 0.2);
+                        let note = self.melody[self.melody_index];
+                        //play chord
+                        self.synth.note_on(note, 1.0);
+                        self.synth.note_on(note + 4, 1.0);
+                        self.synth.note_on(note + 7, 1.0);
+                        self.release_clock = clock
+                            + sample_timing
+                                .duration_to_sample_count(quarter_duration * note_length - 0.1);
                     }
                     self.current_note_quarter_count += 1;
                 }
 
-                let note = self.melody[self.melody_index];
-
-                self.synth.base_generator.frequency = midi_id_to_frequency(note);
-                let mut poly_sample = self.synth.next_sample(&sample_timing) * (1.0 / 3.0);
-
-                self.synth.base_generator.frequency = midi_id_to_frequency(note + 4);
-                poly_sample += self.synth.next_sample(&sample_timing) * (1.0 / 3.0);
+                if clock == self.release_clock {
+                    self.synth.all_notes_off();
+                }
 
-                self.synth.base_generator.frequency = midi_id_to_frequency(note + 7);
-                poly_sample += self.synth.next_sample(&sample_timing) * (1.0 / 3.0);
+                let mut poly_sample = self.synth.next_sample(sample_timing);
 
                 //make stereo
                 poly_sample.polify(2);
@@ -271,17 +342,15 @@ mod tests {
             }
         }
 
-        let mut cpal = Cpal::new().unwrap();
-
         let mut master_patch = MasterPatch::default();
 
         let patch = MyPatch {
-            synth: BasicSynthesizer::new(
+            synth: PolySynthesizer::new(
                 TriangleGenerator::default(),
-                AdsrGenerator::new(0.05, 0.05, 0.7, 0.2, 0.1),
-                0.1,
+                AdsrGenerator::new(0.05, 0.05, 0.7, 0.0, 0.1),
+                3,
+                0.1 / 3.0,
             ),
-            delay: Delay::new(0.3, 0.5),
             melody: vec![
                 76, 74, 72, 74, 76, 76, 76, 74, 74, 74, 76, 79, 79, 76, 74, 72, 74, 76, 76, 76, 76,
                 74, 74, 76, 74, 72,
@@ -294,7 +363,12 @@ mod tests {
 
         master_patch.add_patch(patch);
 
-        cpal.play_patch(&mut master_patch);
+        let buffer = render_to_buffer(&mut master_patch, SAMPLE_RATE, 4.0);
+        assert_eq!(buffer.len(), 4 * SAMPLE_RATE as usize);
+        assert!(buffer.iter().all(|poly_sample| poly_sample[0] == poly_sample[1]));
+        assert!(peak(&buffer, 0) > 0.01);
+        //three voices at a third of volume 0.1 each
+        assert!(peak(&buffer, 0) <= 0.1);
     }
 
     #[test]
@@ -306,6 +380,7 @@ mod tests {
             adsr_oscope: Oscilloscope,
             triangle_gen: TriangleGenerator,
             triangle_oscope: Oscilloscope,
+            output_dir: std::path::PathBuf,
         }
 
         const FREQ: f32 = 440.0;
@@ -313,28 +388,28 @@ mod tests {
 
         impl Patch for MyPatch {
             fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
-                let mut sine = self.sine_gen.generate(&sample_timing);
-                sine = self.sine_oscope.process(&sample_timing, sine);
+                let mut sine = self.sine_gen.generate(sample_timing);
+                sine = self.sine_oscope.process(sample_timing, sine);
 
-                let mut triangle = self.triangle_gen.generate(&sample_timing);
-                triangle = self.triangle_oscope.process(&sample_timing, triangle);
+                let mut triangle = self.triangle_gen.generate(sample_timing);
+                triangle = self.triangle_oscope.process(sample_timing, triangle);
 
                 let sample_count = sample_timing.duration_to_sample_count(DURATION);
 
                 if sample_timing.clock == sample_count - 1 {
-                    self.sine_oscope.plot("oscilloscope_output/sine.png").unwrap();
-                    self.triangle_oscope.plot("oscilloscope_output/triangle.png").unwrap();
+                    self.sine_oscope.plot(self.output_dir.join("sine.png")).unwrap();
+                    self.triangle_oscope.plot(self.output_dir.join("triangle.png")).unwrap();
                 }
 
-                let mut adsr = self.adsr_gen.generate(&sample_timing);
-                adsr = self.adsr_oscope.process(&sample_timing, adsr);
+                let mut adsr = self.adsr_gen.generate(sample_timing);
+                adsr = self.adsr_oscope.process(sample_timing, adsr);
 
                 let sample_count =
                     sample_timing.duration_to_sample_count(self.adsr_gen.total_duration() * 1.05);
 
                 if sample_timing.clock == sample_count - 1 {
-                    self.adsr_oscope.plot("oscilloscope_output/adsr.png").unwrap();
-                    std::process::exit(0);
+                    self.adsr_oscope.plot(self.output_dir.join("adsr.png")).unwrap();
+                    return poly_sample!();
                 }
 
                 let sample_value = sine[0] * triangle[0] * adsr[0] * 0.1;
@@ -342,7 +417,9 @@ mod tests {
             }
         }
 
-        let mut cpal = Cpal::new().unwrap();
+        //plots go to a scratch directory, the checked in plots are left untouched
+        let output_dir = std::env::temp_dir().join("dawrs_plot_test");
+        std::fs::create_dir_all(&output_dir).unwrap();
 
         let mut master_patch = MasterPatch::default();
 
@@ -353,10 +430,1368 @@ mod tests {
             triangle_oscope: Oscilloscope::new(DURATION, DURATION / 1000.0, 0, 512, 1000.0),
             adsr_gen: AdsrGenerator::new(0.05, 0.05, 0.7, 0.2, 0.1),
             adsr_oscope: Oscilloscope::new(0.05 + 0.05 + 0.2 + 0.1, 0.01, 0, 512, 1.0),
+            output_dir: output_dir.clone(),
         };
 
         master_patch.add_patch(patch);
 
-        cpal.play_patch(&mut master_patch);
+        let buffer = render_to_buffer(&mut master_patch, SAMPLE_RATE, 10.0);
+        let adsr_duration = 0.05 + 0.05 + 0.2 + 0.1;
+        assert_eq!(buffer.len() + 1, (SAMPLE_RATE * adsr_duration * 1.05) as usize);
+        //fades out completely
+        assert_abs_diff_eq!(buffer.last().unwrap()[0], 0.0);
+
+        //width and height from the PNG header
+        let size = |name: &str| {
+            let png = std::fs::read(output_dir.join(name)).unwrap();
+            assert_eq!(png[..8], b"\x89PNG\r\n\x1a\n"[..]);
+            let read_u32 = |offset: usize| {
+                u32::from_be_bytes([png[offset], png[offset + 1], png[offset + 2], png[offset + 3]])
+            };
+            (read_u32(16), read_u32(20))
+        };
+        assert_eq!(size("sine.png"), ((1000.0 * DURATION * 512.0) as u32, 512));
+        assert_eq!(size("triangle.png"), ((1000.0 * DURATION * 512.0) as u32, 512));
+        assert_eq!(size("adsr.png"), ((adsr_duration * 512.0) as u32, 512));
+    }
+
+    #[test]
+    fn render_wav() {
+        struct MyPatch {
+            sine_gen: SineGenerator,
+            remaining_samples: usize,
+        }
+
+        impl Patch for MyPatch {
+            fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+                self.remaining_samples -= 1;
+                let mut poly_sample = self.sine_gen.generate(sample_timing) * 0.5;
+                poly_sample.polify(2);
+                poly_sample
+            }
+
+            fn state(&self) -> PatchState {
+                if self.remaining_samples == 0 {
+                    PatchState::Finished
+                } else {
+                    PatchState::Running
+                }
+            }
+        }
+
+        let mut master_patch = MasterPatch::default();
+        master_patch.add_patch(MyPatch {
+            sine_gen: SineGenerator::new(440.0),
+            remaining_samples: 24000,
+        });
+
+        let path = std::env::temp_dir().join("dawrs_render_wav.wav");
+        let renderer = WavRenderer::new(48000, 2, BitDepth::Int24);
+        let frames = renderer.render_patch(&mut master_patch, &path).unwrap();
+        assert_eq!(frames, 24000);
+
+        let reader = hound::WavReader::open(&path).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.channels, 2);
+        assert_eq!(spec.bits_per_sample, 24);
+        assert_eq!(reader.duration(), 24000);
+        let peak = reader.into_samples::<i32>().map(|s| s.unwrap().abs()).max().unwrap();
+        assert_abs_diff_eq!(peak as f32 / 8_388_607.0, 0.5, epsilon = 0.001);
+    }
+
+    #[test]
+    fn gated_envelope() {
+        struct MyPatch {
+            synth: BasicSynthesizer<SineGenerator>,
+        }
+
+        impl Patch for MyPatch {
+            fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+                if sample_timing.is_time(0.0) {
+                    self.synth.note_on();
+                } else if sample_timing.is_time(0.5) {
+                    //release while still in the attack
+                    self.synth.note_off();
+                } else if sample_timing.is_time(0.75) {
+                    //retrigger while still in the release
+                    self.synth.note_on();
+                } else if sample_timing.is_time(3.0) {
+                    self.synth.note_off();
+                }
+                let adsr = self.synth.adsr.level();
+                let poly_sample = self.synth.next_sample(sample_timing);
+                poly_sample!([poly_sample[0], adsr])
+            }
+        }
+
+        let mut patch = MyPatch {
+            synth: BasicSynthesizer::new(
+                SineGenerator::new(440.0),
+                AdsrGenerator::new(1.0, 0.5, 0.5, 0.0, 1.0),
+                1.0,
+            ),
+        };
+
+        let buffer = render_to_buffer(&mut patch, SAMPLE_RATE, 5.0);
+        let envelope_at = |time: f32| buffer[(time * SAMPLE_RATE) as usize][1];
+        assert_abs_diff_eq!(envelope_at(0.5), 0.5, epsilon = 0.001);
+        //released from 0.5 instead of the sustain level
+        assert_abs_diff_eq!(envelope_at(0.75), 0.5 * 0.75, epsilon = 0.001);
+        //attack continues from the level of the release
+        assert_abs_diff_eq!(envelope_at(1.25), 0.375 + 0.625 * 0.5, epsilon = 0.001);
+        //held at sustain level until the gate closes
+        assert_abs_diff_eq!(envelope_at(2.9), 0.5, epsilon = 0.001);
+        assert_abs_diff_eq!(envelope_at(3.5), 0.25, epsilon = 0.001);
+        assert_abs_diff_eq!(envelope_at(4.5), 0.0);
+        assert!(!patch.synth.adsr.is_active());
+    }
+
+    #[test]
+    fn voice_stealing() {
+        let mut synth = PolySynthesizer::new(
+            SineGenerator::default(),
+            AdsrGenerator::new(0.01, 0.0, 1.0, 0.0, 0.5),
+            2,
+            1.0,
+        );
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut play = |synth: &mut PolySynthesizer<SineGenerator>, duration: f32| {
+            let buffer: Vec<_> = (0..sample_timing.duration_to_sample_count(duration))
+                .map(|_| {
+                    let poly_sample = synth.next_sample(&sample_timing);
+                    sample_timing.tick();
+                    poly_sample
+                })
+                .collect();
+            buffer
+        };
+
+        synth.note_on(69, 1.0);
+        let buffer = play(&mut synth, 0.5);
+        assert_abs_diff_eq!(zero_crossing_frequency(&buffer, 0), 440.0, epsilon = 2.0);
+
+        synth.note_on(81, 1.0);
+        synth.note_on(57, 1.0);
+        //oldest voice playing 69 was stolen
+        assert_eq!(synth.active_voice_count(), 2);
+        synth.note_off(69);
+        play(&mut synth, 1.0);
+        assert_eq!(synth.active_voice_count(), 2);
+
+        synth.voice_stealing = VoiceStealing::SameNote;
+        synth.note_on(57, 0.5);
+        synth.note_off(81);
+        synth.note_off(57);
+        play(&mut synth, 1.0);
+        assert_eq!(synth.active_voice_count(), 0);
+
+        //a releasing note is retriggered on its own voice instead of stealing the oldest one
+        synth.note_on(64, 1.0);
+        synth.note_on(60, 1.0);
+        play(&mut synth, 0.1);
+        synth.note_off(60);
+        play(&mut synth, 0.1);
+        synth.note_on(60, 1.0);
+        synth.note_off(60);
+        play(&mut synth, 1.0);
+        //64 is still held
+        assert_eq!(synth.active_voice_count(), 1);
+        synth.all_notes_off();
+        play(&mut synth, 1.0);
+        assert_eq!(synth.active_voice_count(), 0);
+
+        //silent, but still running
+        assert_eq!(play(&mut synth, 0.1)[0].len(), 1);
+    }
+
+    #[test]
+    fn biquad() {
+        struct MyPatch {
+            saw_gen: SawGenerator,
+            adsr_gen: AdsrGenerator,
+            filter: Biquad,
+        }
+
+        impl Patch for MyPatch {
+            fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+                //sweep the cutoff every sample
+                let adsr = self.adsr_gen.generate(sample_timing)[0];
+                self.filter.cutoff = 100.0 + adsr * 10000.0;
+                let saw = self.saw_gen.generate(sample_timing);
+                self.filter.process(sample_timing, saw)
+            }
+        }
+
+        let mut patch = MyPatch {
+            saw_gen: SawGenerator::new(110.0),
+            adsr_gen: AdsrGenerator::new(0.01, 0.1, 0.0, 0.0, 0.0),
+            filter: Biquad::low_pass(100.0, 4.0),
+        };
+        let buffer = render_to_buffer(&mut patch, SAMPLE_RATE, 1.0);
+        assert!(buffer.iter().all(|poly_sample| poly_sample[0].is_finite()));
+        assert!(peak(&buffer, 0) < 4.0);
+
+        let filtered_rms = |filter_type: BiquadType, frequency: f32| {
+            let mut filter = Biquad::new(filter_type, 1000.0, FRAC_1_SQRT_2, 6.0);
+            let mut sine_gen = SineGenerator::new(frequency);
+            let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+            let buffer: Vec<_> = (0..SAMPLE_RATE as usize)
+                .map(|_| {
+                    let sine = sine_gen.generate(&sample_timing);
+                    sample_timing.tick();
+                    filter.process(&sample_timing, sine)
+                })
+                .collect();
+            rms(&buffer[1000..], 0) * 2.0f32.sqrt()
+        };
+        assert!(filtered_rms(BiquadType::LowPass, 100.0) > 0.99);
+        assert!(filtered_rms(BiquadType::LowPass, 10000.0) < 0.02);
+        assert!(filtered_rms(BiquadType::HighPass, 100.0) < 0.02);
+        assert_abs_diff_eq!(filtered_rms(BiquadType::BandPass, 1000.0), 1.0, epsilon = 0.01);
+        assert!(filtered_rms(BiquadType::Notch, 1000.0) < 0.01);
+        //gain of 6 dB
+        assert_abs_diff_eq!(filtered_rms(BiquadType::Peaking, 1000.0), 1.995, epsilon = 0.01);
+        assert_abs_diff_eq!(filtered_rms(BiquadType::LowShelf, 20.0), 1.995, epsilon = 0.01);
+        assert_abs_diff_eq!(filtered_rms(BiquadType::HighShelf, 20.0), 1.0, epsilon = 0.01);
+
+        //switching the type of a settled filter, per sample and in blocks
+        for block_size in [1, 64].iter() {
+            let mut filter = Biquad::low_pass(1000.0, FRAC_1_SQRT_2);
+            let mut sine_gen = SineGenerator::new(100.0);
+            let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+            let mut buffer = vec![poly_sample!(); SAMPLE_RATE as usize];
+            for (i, block) in buffer.chunks_mut(*block_size).enumerate() {
+                if i * block_size >= SAMPLE_RATE as usize / 2 {
+                    filter.filter_type = BiquadType::HighPass;
+                }
+                sine_gen.generate_block(&sample_timing, block);
+                filter.process_block(&sample_timing, block);
+                sample_timing.clock += block.len();
+            }
+            assert!(rms(&buffer[12000..24000], 0) * 2.0f32.sqrt() > 0.99);
+            assert!(rms(&buffer[36000..], 0) * 2.0f32.sqrt() < 0.02);
+        }
+    }
+
+    #[test]
+    fn resonant_filters() {
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut svf = StateVariableFilter::new(StateVariableOutput::LowPass, 1000.0, 0.7);
+        let mut ladder = LadderFilter::new(1000.0, 1.2, 1.0);
+        let mut saw_gen = SawGenerator::new(55.0);
+        let mut sweep = SineGenerator::new(50.0);
+        let mut outputs = Vec::new();
+        let mut ladder_outputs = Vec::new();
+        for clock in 0..SAMPLE_RATE as usize {
+            //fast cutoff sweeps between 20 Hz and 20 kHz
+            let cutoff = 20.0 * 1000.0f32.powf(sweep.generate(&sample_timing)[0] * 0.5 + 0.5);
+            svf.cutoff = cutoff;
+            let saw = saw_gen.generate(&sample_timing);
+            outputs.push((saw[0], svf.process_all(&sample_timing, saw.clone())));
+
+            //excite with a short burst, then let the ladder self-oscillate
+            let input = if clock < 100 { saw } else { poly_sample!([0.0]) };
+            ladder_outputs.push(ladder.process(&sample_timing, input));
+            sample_timing.tick();
+        }
+
+        for (input, output) in &outputs {
+            for poly_sample in &[&output.low_pass, &output.band_pass, &output.high_pass] {
+                assert!(poly_sample[0].is_finite() && poly_sample[0].abs() < 10.0);
+            }
+            //outputs sum up to the input
+            let sum = output.low_pass[0] + output.band_pass[0] / 0.7 + output.high_pass[0];
+            assert_abs_diff_eq!(sum, input, epsilon = 0.0001);
+        }
+
+        let self_oscillation = &ladder_outputs[SAMPLE_RATE as usize / 2..];
+        assert!(peak(self_oscillation, 0) > 0.1 && peak(self_oscillation, 0) <= 1.0);
+        assert_abs_diff_eq!(zero_crossing_frequency(self_oscillation, 0), 1000.0, epsilon = 50.0);
+    }
+
+    #[test]
+    fn reverb() {
+        let mut reverb = Reverb::new(0.8, 0.5, 0.05, 1.0, 1.0, 0.0);
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let buffer: Vec<_> = (0..SAMPLE_RATE as usize * 2)
+            .map(|clock| {
+                //mono impulse
+                let input = poly_sample!([if clock == 0 { 1.0 } else { 0.0 }]);
+                let output = reverb.process(&sample_timing, input);
+                sample_timing.tick();
+                output
+            })
+            .collect();
+
+        assert!(buffer.iter().all(|poly_sample| poly_sample.len() == 2));
+        //silent during the pre-delay
+        let pre_delay_size = sample_timing.duration_to_sample_count(0.05);
+        assert_eq!(peak(&buffer[..pre_delay_size], 0), 0.0);
+        //decaying tail, which differs between the channels
+        let tail = &buffer[SAMPLE_RATE as usize / 2..SAMPLE_RATE as usize];
+        assert!(rms(tail, 0) > 0.0001 && rms(tail, 1) > 0.0001);
+        assert!(rms(&buffer[SAMPLE_RATE as usize..], 0) < rms(tail, 0));
+        assert!(tail.iter().any(|poly_sample| poly_sample[0] != poly_sample[1]));
+    }
+
+    #[test]
+    fn convolver() {
+        //stereo impulse response, decaying noise on the left and an echo on the right
+        let mut noise_gen = NoiseGenerator::new(NoiseColor::White, 0);
+        let ir_timing = SampleTiming::new(SAMPLE_RATE);
+        let impulse_response: Vec<_> = (0..5000)
+            .map(|i| {
+                let left = noise_gen.generate(&ir_timing)[0] * (-(i as f32) / 1000.0).exp();
+                let right = if i == 3000 { 0.5 } else { 0.0 };
+                poly_sample!([left, right])
+            })
+            .collect();
+
+        let mut convolver = Convolver::new(impulse_response.clone(), SAMPLE_RATE, 256);
+        convolver.dry = 0.5;
+        let latency = convolver.latency();
+        let mut saw_gen = SawGenerator::new(110.0);
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let (input, output): (Vec<_>, Vec<_>) = (0..10000)
+            .map(|_| {
+                let saw = saw_gen.generate(&sample_timing)[0];
+                let output = convolver.process(&sample_timing, poly_sample!([saw]));
+                sample_timing.tick();
+                (saw, output)
+            })
+            .unzip();
+
+        for n in (latency..10000).step_by(37) {
+            //direct convolution
+            let expected = |channel: usize| {
+                (0..impulse_response.len().min(n - latency + 1))
+                    .map(|k| impulse_response[k][channel] * input[n - latency - k])
+                    .sum::<f32>()
+                    + input[n - latency] * 0.5
+            };
+            assert_abs_diff_eq!(output[n][0], expected(0), epsilon = 0.001);
+            assert_abs_diff_eq!(output[n][1], expected(1), epsilon = 0.001);
+        }
+    }
+
+    #[test]
+    fn delay() {
+        let impulse_response = |delay: &mut Delay| -> Vec<PolySample> {
+            let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+            (0..SAMPLE_RATE as usize)
+                .map(|clock| {
+                    let input = poly_sample!([if clock == 0 { 1.0 } else { 0.0 }]);
+                    let output = delay.process(&sample_timing, input);
+                    sample_timing.tick();
+                    output
+                })
+                .collect()
+        };
+
+        let mut delay = Delay::tempo_synced(120.0, 1.0 / 64.0, 0.5);
+        assert_abs_diff_eq!(delay.delay, 1.0 / 32.0);
+        let buffer = impulse_response(&mut delay);
+        assert_abs_diff_eq!(buffer[0][0], 1.0);
+        assert_abs_diff_eq!(buffer[1500][0], 0.5, epsilon = 0.0001);
+        assert_abs_diff_eq!(buffer[3000][0], 0.25, epsilon = 0.0001);
+        assert_abs_diff_eq!(peak(&buffer[1..1500], 0), 0.0);
+
+        //the default delay passes the input through unchanged
+        let buffer = impulse_response(&mut Delay::default());
+        assert_abs_diff_eq!(buffer[0][0], 1.0);
+        assert_abs_diff_eq!(peak(&buffer[1..], 0), 0.0);
+
+        //echoes alternate between the channels, starting on the left
+        delay.reset();
+        delay.ping_pong = true;
+        delay.dry = 0.0;
+        let buffer = impulse_response(&mut delay);
+        assert!(buffer.iter().all(|poly_sample| poly_sample.len() == 2));
+        assert_abs_diff_eq!(buffer[1500][0], 0.5, epsilon = 0.0001);
+        assert_abs_diff_eq!(buffer[1500][1], 0.0, epsilon = 0.0001);
+        assert_abs_diff_eq!(buffer[3000][0], 0.0, epsilon = 0.0001);
+        assert_abs_diff_eq!(buffer[3000][1], 0.25, epsilon = 0.0001);
+
+        //filtered and saturated feedback path stays stable even above unity feedback
+        let mut delay = Delay::new(0.01, 1.5);
+        delay.high_cut = Some(2000.0);
+        delay.saturation = 1.0;
+        let buffer = impulse_response(&mut delay);
+        assert!(peak(&buffer[SAMPLE_RATE as usize / 2..], 0) < 3.0);
+
+        //modulating the delay time glides instead of jumping
+        let mut delay = Delay::new(0.01, 0.5);
+        let mut sine_gen = SineGenerator::new(100.0);
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let buffer: Vec<_> = (0..SAMPLE_RATE as usize / 2)
+            .map(|clock| {
+                delay.delay = if clock < SAMPLE_RATE as usize / 4 { 0.01 } else { 0.0123 };
+                let output = delay.process(&sample_timing, sine_gen.generate(&sample_timing));
+                sample_timing.tick();
+                output
+            })
+            .collect();
+        let max_step =
+            buffer.windows(2).map(|window| (window[1][0] - window[0][0]).abs()).fold(0.0, f32::max);
+        assert!(max_step < 0.05);
+    }
+
+    #[test]
+    fn modulation_effects() {
+        fn run<E: Effect, G: Generator>(effect: &mut E, generator: &mut G) -> Vec<PolySample> {
+            let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+            (0..SAMPLE_RATE as usize / 2)
+                .map(|_| {
+                    let input = generator.generate(&sample_timing);
+                    let output = effect.process(&sample_timing, input);
+                    sample_timing.tick();
+                    output
+                })
+                .collect()
+        }
+        let impulse = || {
+            let mut clock = 0;
+            move |_: &SampleTiming| {
+                clock += 1;
+                poly_sample!([if clock == 1 { 1.0 } else { 0.0 }])
+            }
+        };
+        let constant = |value: f32| move |_: &SampleTiming| poly_sample!([value]);
+
+        //single voice held at the center by the modulator is a plain delay
+        let mut chorus = Chorus::new(1, 0.8, 0.005, 0.5);
+        chorus.modulator = Some(Box::new(constant(0.0)));
+        let buffer = run(&mut chorus, &mut impulse());
+        let delay_size = (chorus.delay * SAMPLE_RATE).round() as usize;
+        assert_abs_diff_eq!(buffer[0][0], 1.0);
+        assert_abs_diff_eq!(buffer[delay_size][0], 0.5, epsilon = 0.0001);
+        assert_abs_diff_eq!(buffer[delay_size][1], 0.5, epsilon = 0.0001);
+
+        //spread voices make mono input stereo
+        let mut chorus = Chorus::default();
+        let buffer = run(&mut chorus, &mut SawGenerator::new(220.0));
+        assert!(buffer.iter().all(|poly_sample| poly_sample.len() == 2));
+        assert!(buffer.iter().any(|poly_sample| poly_sample[0] != poly_sample[1]));
+
+        //feedback echoes at the delay set by the modulator
+        let mut flanger = Flanger::new(0.25, 0.002, 0.5);
+        flanger.dry = 0.0;
+        flanger.modulator = Some(Box::new(constant(1.0)));
+        let buffer = run(&mut flanger, &mut impulse());
+        let delay_size = ((flanger.delay + flanger.depth) * SAMPLE_RATE).round() as usize;
+        assert_abs_diff_eq!(buffer[delay_size][0], 1.0, epsilon = 0.0001);
+        assert_abs_diff_eq!(buffer[2 * delay_size][0], 0.5, epsilon = 0.0001);
+        assert_abs_diff_eq!(peak(&buffer[..delay_size], 0), 0.0);
+
+        //with the all-pass filters at 1000 Hz, four stages shift ~415 Hz by half a period
+        let mut phaser = Phaser::new(4, 0.5, 1000.0, 4000.0);
+        phaser.modulator = Some(Box::new(constant(-1.0)));
+        let buffer = run(&mut phaser, &mut SineGenerator::new(414.7));
+        assert!(rms(&buffer[1000..], 0) < 0.01);
+        let buffer = run(&mut phaser, &mut SineGenerator::new(1000.0));
+        assert_abs_diff_eq!(rms(&buffer[1000..], 0), 2.0 * FRAC_1_SQRT_2, epsilon = 0.01);
+
+        //the internal LFO sweeps the notch
+        phaser.modulator = None;
+        let buffer = run(&mut phaser, &mut SineGenerator::new(414.7));
+        assert!(rms(&buffer, 0) > 0.1);
+    }
+
+    #[test]
+    fn dynamics() {
+        let compressor = Compressor::new(-18.0, 4.0, 0.01, 0.1);
+        assert_abs_diff_eq!(compressor.gain(-30.0), 0.0);
+        assert_abs_diff_eq!(compressor.gain(-18.0), -0.5625);
+        assert_abs_diff_eq!(compressor.gain(-6.0), -9.0);
+
+        //full scale sine compressed by 12 dB above the threshold
+        let mut compressor = Compressor::new(-12.0, 4.0, 0.001, 0.5);
+        compressor.knee = 0.0;
+        let mut sine_gen = SineGenerator::new(440.0);
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let buffer: Vec<_> = (0..SAMPLE_RATE as usize)
+            .map(|_| {
+                let output = compressor.process(&sample_timing, sine_gen.generate(&sample_timing));
+                sample_timing.tick();
+                output
+            })
+            .collect();
+        assert_abs_diff_eq!(compressor.gain_reduction(), 9.0, epsilon = 0.5);
+        assert_abs_diff_eq!(peak(&buffer[SAMPLE_RATE as usize / 2..], 0), 0.355, epsilon = 0.02);
+
+        //louder left channel, both channels reduced equally and never above the ceiling
+        let mut limiter = Limiter::new(-1.0, 0.005, 0.05);
+        let mut saw_gen = SawGenerator::new(110.0);
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let (input, output): (Vec<_>, Vec<_>) = (0..SAMPLE_RATE as usize / 2)
+            .map(|_| {
+                let saw = saw_gen.generate(&sample_timing)[0];
+                let output = limiter.process(&sample_timing, poly_sample!([saw * 3.0, saw]));
+                sample_timing.tick();
+                (saw, output)
+            })
+            .unzip();
+        let latency = limiter.latency(SAMPLE_RATE);
+        assert!(peak(&output, 0) <= 10.0f32.powf(-1.0 / 20.0) + 0.0001);
+        assert!(peak(&output, 0) > 0.85);
+        for (input, output) in input.iter().zip(&output[latency..]) {
+            assert_abs_diff_eq!(output[0], output[1] * 3.0, epsilon = 0.0001);
+            assert!(output[1].abs() <= input.abs());
+        }
+
+        //quiet signal is expanded down, loud signal passes
+        let mut expander = Expander::new(-40.0, 2.0, 0.001, 0.05);
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut sine_gen = SineGenerator::new(440.0);
+        let mut run = |effect: &mut dyn Effect, amplitude: f32| -> Vec<PolySample> {
+            (0..SAMPLE_RATE as usize / 4)
+                .map(|_| {
+                    let input = sine_gen.generate(&sample_timing) * amplitude;
+                    let output = effect.process(&sample_timing, input);
+                    sample_timing.tick();
+                    output
+                })
+                .collect()
+        };
+        let loud = run(&mut expander, 0.5);
+        assert_abs_diff_eq!(rms(&loud[1000..], 0), 0.5 * FRAC_1_SQRT_2, epsilon = 0.01);
+        let quiet = run(&mut expander, 0.001);
+        assert!(expander.gain_reduction() > 15.0);
+        //the RMS detection takes a while to fall below the threshold
+        assert!(rms(&quiet[SAMPLE_RATE as usize * 3 / 16..], 0) < 0.0001);
+
+        //gate stays open for the hold time, then closes
+        let mut noise_gate = NoiseGate::new(-40.0, 0.0005, 0.05, 0.01);
+        let loud = run(&mut noise_gate, 0.5);
+        assert_abs_diff_eq!(rms(&loud[1000..], 0), 0.5 * FRAC_1_SQRT_2, epsilon = 0.01);
+        let quiet = run(&mut noise_gate, 0.005);
+        assert_abs_diff_eq!(rms(&quiet[..1000], 0), 0.005 * FRAC_1_SQRT_2, epsilon = 0.0005);
+        assert!(rms(&quiet[SAMPLE_RATE as usize / 8..], 0) < 0.00001);
+        assert_abs_diff_eq!(noise_gate.gain_reduction(), 80.0, epsilon = 0.1);
+    }
+
+    #[test]
+    fn sidechain() {
+        struct PumpingPatch {
+            kick_synth: BasicSynthesizer<SineGenerator>,
+            bass_gen: SineGenerator,
+            ducker: Ducker,
+        }
+
+        impl Patch for PumpingPatch {
+            fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+                if sample_timing.is_after_interval(0.5) {
+                    self.kick_synth.play(0.05);
+                }
+                let kick = self.kick_synth.next_sample(sample_timing);
+                let bass = self.bass_gen.generate(sample_timing) * 0.5;
+                self.ducker.process_sidechain(sample_timing, bass, kick)
+            }
+        }
+
+        let mut ducker = Ducker::new(-20.0, 12.0, 0.002, 0.05);
+        //bridges the zero crossings of the kick
+        ducker.hold = 0.02;
+        let mut patch = PumpingPatch {
+            kick_synth: BasicSynthesizer::new(
+                SineGenerator::new(60.0),
+                AdsrGenerator::new(0.001, 0.05, 0.5, 0.05, 0.05),
+                0.8,
+            ),
+            bass_gen: SineGenerator::new(110.0),
+            ducker,
+        };
+        let buffer = render_to_buffer(&mut patch, SAMPLE_RATE, 1.0);
+        let time = |seconds: f32| (seconds * SAMPLE_RATE) as usize;
+        //ducked while the kick plays, recovered before the next kick
+        for kick in &[0.0, 0.5] {
+            assert_abs_diff_eq!(
+                peak(&buffer[time(kick + 0.03)..time(kick + 0.1)], 0),
+                0.126,
+                epsilon = 0.01
+            );
+            assert_abs_diff_eq!(
+                peak(&buffer[time(kick + 0.4)..time(kick + 0.5)], 0),
+                0.5,
+                epsilon = 0.01
+            );
+        }
+
+        //compressor keyed by a louder signal than its input
+        let mut compressor = Compressor::new(-20.0, 4.0, 0.001, 0.1);
+        compressor.knee = 0.0;
+        let sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut output = poly_sample!();
+        for _ in 0..1000 {
+            output = compressor.process_sidechain(
+                &sample_timing,
+                poly_sample!([0.1]),
+                poly_sample!([1.0]),
+            );
+        }
+        assert_abs_diff_eq!(compressor.gain_reduction(), 15.0, epsilon = 0.01);
+        assert_abs_diff_eq!(output[0], 0.1 * 10.0f32.powf(-15.0 / 20.0), epsilon = 0.0001);
+    }
+
+    #[test]
+    fn distortion() {
+        assert_abs_diff_eq!(TransferCurve::SoftClip.shape(0.5), 0.5f32.tanh());
+        assert_abs_diff_eq!(TransferCurve::HardClip.shape(-1.5), -1.0);
+        assert_abs_diff_eq!(TransferCurve::Foldback.shape(1.25), 0.75);
+        assert_abs_diff_eq!(TransferCurve::Foldback.shape(-2.5), 0.5);
+        assert_abs_diff_eq!(TransferCurve::Tube.shape(0.0), 0.0);
+        assert!((TransferCurve::Tube.shape(2.0) + TransferCurve::Tube.shape(-2.0)).abs() > 0.1);
+        assert_abs_diff_eq!(TransferCurve::custom(|x| x * x).shape(-0.5), 0.25);
+        let table = TransferCurve::table(vec![-1.0, 0.0, 0.5]);
+        assert_abs_diff_eq!(table.shape(0.5), 0.25);
+        assert_abs_diff_eq!(table.shape(2.0), 0.5);
+
+        //amplitude of `frequency` by correlating with a sine and cosine
+        fn amplitude_at(buffer: &[PolySample], frequency: f32) -> f32 {
+            let (sin, cos) = buffer.iter().enumerate().fold((0.0, 0.0), |(sin, cos), (i, s)| {
+                let phase = 2.0 * std::f32::consts::PI * frequency * i as f32 / SAMPLE_RATE;
+                (sin + s[0] * phase.sin(), cos + s[0] * phase.cos())
+            });
+            2.0 * (sin * sin + cos * cos).sqrt() / buffer.len() as f32
+        }
+        let run = |oversampling: usize| {
+            let mut distortion = Distortion::new(TransferCurve::HardClip, 20.0);
+            distortion.oversampling = oversampling;
+            distortion.output_gain = -6.0;
+            let mut sine_gen = SineGenerator::new(5000.0);
+            let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+            let buffer: Vec<_> = (0..SAMPLE_RATE as usize)
+                .map(|_| {
+                    let output =
+                        distortion.process(&sample_timing, sine_gen.generate(&sample_timing));
+                    sample_timing.tick();
+                    output
+                })
+                .collect();
+            buffer
+        };
+
+        //9th harmonic at 45 kHz aliases to 3 kHz
+        let aliased = run(1);
+        assert_abs_diff_eq!(
+            amplitude_at(&aliased, 5000.0),
+            0.5 * 4.0 / std::f32::consts::PI,
+            epsilon = 0.05
+        );
+        assert!(amplitude_at(&aliased, 3000.0) > 0.02);
+        let oversampled = run(8);
+        assert_abs_diff_eq!(
+            amplitude_at(&oversampled, 5000.0),
+            0.5 * 4.0 / std::f32::consts::PI,
+            epsilon = 0.05
+        );
+        assert!(amplitude_at(&oversampled, 3000.0) < amplitude_at(&aliased, 3000.0) / 10.0);
+    }
+
+    #[test]
+    fn lo_fi() {
+        let sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut bitcrusher = Bitcrusher::new(2.0);
+        let output = bitcrusher.process(&sample_timing, poly_sample!([0.3, -0.9]));
+        assert_eq!((output[0], output[1]), (0.5, -1.0));
+        //fractional bit depth
+        bitcrusher.bits = 1.5;
+        let output = bitcrusher.process(&sample_timing, poly_sample!([0.5]));
+        assert_abs_diff_eq!(output[0], 2.0 / 1.5f32.exp2());
+
+        //dithering keeps the average of a signal between two steps
+        bitcrusher.bits = 2.0;
+        let average = |bitcrusher: &mut Bitcrusher| {
+            (0..10000)
+                .map(|_| bitcrusher.process(&sample_timing, poly_sample!([0.1]))[0])
+                .sum::<f32>()
+                / 10000.0
+        };
+        assert_abs_diff_eq!(average(&mut bitcrusher), 0.0);
+        bitcrusher.dither = true;
+        assert_abs_diff_eq!(average(&mut bitcrusher), 0.1, epsilon = 0.01);
+
+        let mut downsampler = Downsampler::new(12000.0);
+        let mut sine_gen = SineGenerator::new(440.0);
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut run = |downsampler: &mut Downsampler| -> Vec<PolySample> {
+            (0..SAMPLE_RATE as usize)
+                .map(|_| {
+                    let output =
+                        downsampler.process(&sample_timing, sine_gen.generate(&sample_timing));
+                    sample_timing.tick();
+                    output
+                })
+                .collect()
+        };
+        let changes = |buffer: &[PolySample]| {
+            buffer.windows(2).filter(|window| window[0][0] != window[1][0]).count()
+        };
+        let buffer = run(&mut downsampler);
+        assert!(buffer
+            .chunks(4)
+            .all(|chunk| chunk.iter().all(|poly_sample| poly_sample[0] == chunk[0][0])));
+        assert_abs_diff_eq!(changes(&buffer) as f32, 12000.0, epsilon = 2.0);
+        //fractional rate
+        downsampler.sample_rate = 19200.0;
+        assert_abs_diff_eq!(changes(&run(&mut downsampler)) as f32, 19200.0, epsilon = 2.0);
+    }
+
+    #[test]
+    fn panning() {
+        let sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let center_db = |law: PanLaw| 20.0 * law.gains(0.0).0.log10();
+        assert_abs_diff_eq!(center_db(PanLaw::Linear), -6.02, epsilon = 0.01);
+        assert_abs_diff_eq!(center_db(PanLaw::ConstantPower), -3.01, epsilon = 0.01);
+        assert_abs_diff_eq!(center_db(PanLaw::Compromise), -4.52, epsilon = 0.01);
+        for law in &[PanLaw::Linear, PanLaw::ConstantPower, PanLaw::Compromise] {
+            assert_eq!(law.gains(-1.0), (1.0, 0.0));
+            let (left, right) = law.gains(0.5);
+            assert!(left < right);
+        }
+        let (left, right) = PanLaw::ConstantPower.gains(0.3);
+        assert_abs_diff_eq!(left * left + right * right, 1.0, epsilon = 0.0001);
+
+        let mut panner = Panner::new(-0.5, PanLaw::Linear);
+        let output = panner.process(&sample_timing, poly_sample!([1.0]));
+        assert_abs_diff_eq!(output[0], 0.75);
+        assert_abs_diff_eq!(output[1], 0.25);
+        //balance keeps the left channel of stereo input unchanged
+        panner.balance = true;
+        let output = panner.process(&sample_timing, poly_sample!([0.8, 0.4]));
+        assert_abs_diff_eq!(output[0], 0.8);
+        assert_abs_diff_eq!(output[1], 0.2);
+
+        let mut poly_sample = poly_sample!([0.8, 0.4]);
+        poly_sample.encode_mid_side();
+        assert_abs_diff_eq!(poly_sample[0], 0.6);
+        assert_abs_diff_eq!(poly_sample[1], 0.2);
+        poly_sample.decode_mid_side();
+        assert_abs_diff_eq!(poly_sample[0], 0.8);
+        assert_abs_diff_eq!(poly_sample[1], 0.4);
+
+        let mut stereo_width = StereoWidth::new(0.0);
+        let output = stereo_width.process(&sample_timing, poly_sample!([0.8, 0.4]));
+        assert_abs_diff_eq!(output[0], 0.6);
+        assert_abs_diff_eq!(output[1], 0.6);
+        stereo_width.width = 2.0;
+        let output = stereo_width.process(&sample_timing, poly_sample!([0.8, 0.4]));
+        assert_abs_diff_eq!(output[0], 1.0);
+        assert_abs_diff_eq!(output[1], 0.2);
+    }
+
+    #[test]
+    fn mixer() {
+        struct ConstantPatch(f32);
+
+        impl Patch for ConstantPatch {
+            fn next_sample(&mut self, _sample_timing: &SampleTiming) -> PolySample {
+                poly_sample!([self.0])
+            }
+        }
+
+        let sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut mixer = Mixer::new();
+        let left = mixer.add_track(ConstantPatch(0.5));
+        let right = mixer.add_track(ConstantPatch(0.2));
+        let track = mixer.track_mut(left).unwrap();
+        track.gain = -20.0 * 2.0f32.log10();
+        track.panner.pan = -1.0;
+        mixer.track_mut(right).unwrap().panner.pan = 1.0;
+        let assert_output = |mixer: &mut Mixer, expected: [f32; 2]| {
+            let output = mixer.next_sample(&sample_timing);
+            assert_abs_diff_eq!(output[0], expected[0], epsilon = 0.0001);
+            assert_abs_diff_eq!(output[1], expected[1], epsilon = 0.0001);
+        };
+        assert_output(&mut mixer, [0.25, 0.2]);
+
+        mixer.track_mut(left).unwrap().muted = true;
+        assert_output(&mut mixer, [0.0, 0.2]);
+        mixer.track_mut(left).unwrap().muted = false;
+        mixer.track_mut(left).unwrap().soloed = true;
+        assert_output(&mut mixer, [0.25, 0.0]);
+        mixer.track_mut(left).unwrap().soloed = false;
+
+        //bus shared by both tracks, made mono by its effect
+        let bus = mixer.add_bus();
+        mixer.bus_mut(bus).unwrap().add_effect(StereoWidth::new(0.0));
+        mixer.track_mut(left).unwrap().set_send(bus, 0.0);
+        mixer.track_mut(right).unwrap().set_send(bus, 0.0);
+        assert_output(&mut mixer, [0.25 + 0.225, 0.2 + 0.225]);
+        mixer.bus_mut(bus).unwrap().gain = -20.0 * 2.0f32.log10();
+        assert_output(&mut mixer, [0.25 + 0.1125, 0.2 + 0.1125]);
+        assert!(mixer.remove_bus(bus).is_some());
+        assert!(mixer.track(left).unwrap().sends.is_empty());
+        assert_output(&mut mixer, [0.25, 0.2]);
+
+        assert!(mixer.replace_patch(right, ConstantPatch(0.4)).is_some());
+        assert_output(&mut mixer, [0.25, 0.4]);
+        assert!(mixer.remove_track(left).is_some());
+        assert!(mixer.track(left).is_none());
+        assert_eq!(mixer.track_ids().collect::<Vec<_>>(), vec![right]);
+        assert_output(&mut mixer, [0.0, 0.4]);
+
+        //inserts of muted tracks keep running, so the echo of a muted impulse is heard
+        struct ImpulsePatch(usize);
+
+        impl Patch for ImpulsePatch {
+            fn next_sample(&mut self, _sample_timing: &SampleTiming) -> PolySample {
+                //finishes after its 600th sample
+                self.0 += 1;
+                match self.0 {
+                    1 => poly_sample!([1.0]),
+                    2..=600 => poly_sample!([0.0]),
+                    601 => poly_sample!(),
+                    _ => panic!("called after finishing"),
+                }
+            }
+        }
+
+        let mut mixer = Mixer::new();
+        let id = mixer.add_track(ImpulsePatch(0));
+        let track = mixer.track_mut(id).unwrap();
+        let mut delay = Delay::new(0.01, 0.5);
+        delay.dry = 0.0;
+        track.add_insert(delay);
+        track.muted = true;
+        track.panner.pan = -1.0;
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut buffer = vec![poly_sample!(); 240];
+        for block in 0..3 {
+            assert_eq!(mixer.next_block(&sample_timing, &mut buffer), buffer.len());
+            sample_timing.clock += buffer.len();
+            mixer.track_mut(id).unwrap().muted = false;
+            if block == 2 {
+                assert_abs_diff_eq!(buffer[0][0], 0.5, epsilon = 0.0001);
+                assert_abs_diff_eq!(peak(&buffer[1..], 0), 0.0, epsilon = 0.0001);
+            }
+        }
+        //finishes with its last track
+        assert_eq!(mixer.state(), PatchState::Finished);
+        assert_eq!(mixer.next_block(&sample_timing, &mut buffer), 0);
+        assert_eq!(Mixer::new().state(), PatchState::Finished);
+    }
+
+    #[test]
+    fn patch_lifecycle() {
+        struct OneShotPatch {
+            sine_gen: SineGenerator,
+            duration: f32,
+            finished: bool,
+        }
+
+        impl Patch for OneShotPatch {
+            fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+                self.finished = sample_timing.sample_clock() >= self.duration;
+                self.sine_gen.generate(sample_timing) * 0.5
+            }
+
+            fn state(&self) -> PatchState {
+                if self.finished {
+                    PatchState::Finished
+                } else {
+                    PatchState::Running
+                }
+            }
+        }
+
+        let one_shot = |duration: f32| OneShotPatch {
+            sine_gen: SineGenerator::new(440.0),
+            duration,
+            finished: false,
+        };
+        let mut master_patch = MasterPatch::new();
+        master_patch.add_patch(one_shot(0.1));
+        master_patch.add_patch(one_shot(0.5));
+        //silent until its first note
+        master_patch.add_patch(BasicSynthesizer::<SineGenerator>::default());
+        assert_eq!(master_patch.state(), PatchState::Running);
+
+        //the short patch ending doesn't stop the others
+        let buffer = render_to_buffer(&mut master_patch, SAMPLE_RATE, 0.3);
+        assert_eq!(buffer.len(), (SAMPLE_RATE * 0.3) as usize);
+        assert_eq!(master_patch.patch_count(), 2);
+        assert_abs_diff_eq!(peak(&buffer[..4800], 0), 1.0, epsilon = 0.01);
+        assert_abs_diff_eq!(peak(&buffer[4900..], 0), 0.5, epsilon = 0.01);
+
+        //only the silent synthesizer is left
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        sample_timing.clock = buffer.len();
+        while master_patch.patch_count() > 1 {
+            master_patch.next_sample(&sample_timing);
+            sample_timing.tick();
+        }
+        assert_eq!(master_patch.state(), PatchState::Silent);
+        master_patch.stop();
+        assert_eq!(master_patch.state(), PatchState::Finished);
+        assert!(render_to_buffer(&mut master_patch, SAMPLE_RATE, 1.0).is_empty());
+
+        //an empty sample on the last frame of a block ends the output
+        struct CountdownPatch(usize);
+
+        impl Patch for CountdownPatch {
+            fn next_sample(&mut self, _sample_timing: &SampleTiming) -> PolySample {
+                if self.0 == 0 {
+                    return poly_sample!();
+                }
+                self.0 -= 1;
+                poly_sample!([1.0])
+            }
+        }
+
+        impl OutPatch for CountdownPatch {}
+
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut output = [0.0f32; 2 * 128];
+        let event = CountdownPatch(63).write_data(&mut output, 2, &mut sample_timing);
+        assert!(matches!(event, Some(CpalEvent::Exit)));
+        assert_eq!(sample_timing.clock, 63);
+        assert!(output[..2 * 63].chunks(2).all(|frame| frame == [1.0, 0.0]));
+        assert!(output[2 * 63..].iter().all(|sample| *sample == 0.0));
+    }
+
+    #[test]
+    fn block_processing() {
+        fn assert_same(per_sample: &[PolySample], block: &[PolySample]) {
+            assert_eq!(per_sample.len(), block.len());
+            for (per_sample, block) in per_sample.iter().zip(block) {
+                assert_eq!(per_sample[..], block[..]);
+            }
+        }
+
+        fn generate(generator: &mut dyn Generator, block_size: Option<usize>) -> Vec<PolySample> {
+            let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+            let mut buffer = vec![poly_sample!(); 1000];
+            match block_size {
+                Some(block_size) => {
+                    for block in buffer.chunks_mut(block_size) {
+                        generator.generate_block(&sample_timing, block);
+                        sample_timing.clock += block.len();
+                    }
+                }
+                None => {
+                    for poly_sample in &mut buffer {
+                        *poly_sample = generator.generate(&sample_timing);
+                        sample_timing.tick();
+                    }
+                }
+            }
+            buffer
+        }
+
+        fn generators() -> Vec<Box<dyn Generator>> {
+            vec![
+                Box::new(SineGenerator::new(440.0)),
+                Box::new(TriangleGenerator::new(440.0)),
+                Box::new(SawGenerator::new(440.0)),
+                Box::new(SquareGenerator::new(440.0)),
+                Box::new(PulseGenerator::new(440.0, 0.2)),
+                Box::new(NoiseGenerator::new(NoiseColor::Pink, 1)),
+                Box::new(NoiseGenerator::new(NoiseColor::Brown, 1)),
+                Box::new(AdsrGenerator::new(0.005, 0.005, 0.5, 0.005, 0.005)),
+                Box::new({
+                    let mut adsr = AdsrGenerator::new(0.005, 0.005, 0.5, 0.005, 0.005);
+                    adsr.gate_on();
+                    adsr
+                }),
+                Box::new({
+                    let mut envelope = BreakpointEnvelope::ahdsr(0.002, 0.001, 0.005, 0.5, 0.005);
+                    envelope.gate_on();
+                    envelope
+                }),
+                Box::new(LfoGenerator::new(LfoShape::Triangle, 100.0)),
+                Box::new(LfoGenerator::new(LfoShape::SampleAndHold, 100.0)),
+                Box::new({
+                    let samples =
+                        (0..300).map(|i| poly_sample!([(i as f32 * 0.1).sin()])).collect();
+                    let mut sample = SampleGenerator::new(samples, 44100.0);
+                    sample.loop_mode = LoopMode::PingPong;
+                    sample.loop_start = 100.0 / 44100.0;
+                    sample
+                }),
+            ]
+        }
+
+        for (mut per_sample, mut block) in generators().into_iter().zip(generators()) {
+            assert_same(&generate(&mut *per_sample, None), &generate(&mut *block, Some(100)));
+        }
+
+        let input = generate(&mut SawGenerator::new(220.0), None);
+        let stereo_input: Vec<PolySample> = input
+            .iter()
+            .enumerate()
+            .map(|(i, poly_sample)| poly_sample!([poly_sample[0], (i as f32 * 0.1).sin()]))
+            .collect();
+        let effects = || -> Vec<Box<dyn Effect>> {
+            vec![
+                Box::new(Biquad::low_pass(1000.0, 2.0)),
+                Box::new(Panner::new(0.3, PanLaw::Compromise)),
+                Box::new(Panner {
+                    balance: true,
+                    ..Panner::new(-0.5, PanLaw::ConstantPower)
+                }),
+                Box::new(StereoWidth::new(1.5)),
+                Box::new(Delay::new(0.001, 0.5)),
+                Box::new({
+                    let mut delay = Delay::new(0.002, 0.7);
+                    delay.ping_pong = true;
+                    delay.low_cut = Some(200.0);
+                    delay.high_cut = Some(5000.0);
+                    delay.saturation = 0.5;
+                    delay
+                }),
+                Box::new(Reverb::new(0.5, 0.5, 0.001, 1.0, 0.3, 1.0)),
+                Box::new(StateVariableFilter::new(StateVariableOutput::Notch, 1000.0, 2.0)),
+                Box::new(LadderFilter::new(1000.0, 0.8, 2.0)),
+                Box::new(Chorus::new(3, 50.0, 0.001, 0.5)),
+                Box::new(Flanger::new(50.0, 0.001, 0.7)),
+                Box::new({
+                    let mut phaser = Phaser::new(4, 50.0, 200.0, 2000.0);
+                    phaser.feedback = 0.5;
+                    phaser
+                }),
+                Box::new(Compressor::new(-12.0, 4.0, 0.001, 0.005)),
+                Box::new(Limiter::new(-6.0, 0.001, 0.005)),
+                Box::new(Expander::new(-6.0, 4.0, 0.001, 0.005)),
+                Box::new(NoiseGate::new(-3.0, 0.001, 0.001, 0.005)),
+                Box::new(Distortion::default()),
+            ]
+        };
+        let process = |effect: &mut dyn Effect, input: &[PolySample], block_size: Option<usize>| {
+            let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+            let mut buffer = input.to_vec();
+            match block_size {
+                Some(block_size) => {
+                    for block in buffer.chunks_mut(block_size) {
+                        effect.process_block(&sample_timing, block);
+                        sample_timing.clock += block.len();
+                    }
+                }
+                None => {
+                    for poly_sample in &mut buffer {
+                        *poly_sample = effect.process(&sample_timing, poly_sample.clone());
+                        sample_timing.tick();
+                    }
+                }
+            }
+            buffer
+        };
+        for input in [&input, &stereo_input].iter() {
+            for (mut per_sample, mut block) in effects().into_iter().zip(effects()) {
+                assert_same(
+                    &process(&mut *per_sample, input, None),
+                    &process(&mut *block, input, Some(100)),
+                );
+            }
+        }
+
+        //the biquad switches between smoothed and settled coefficients
+        let mut per_sample = Biquad::low_pass(1000.0, 2.0);
+        let mut block = per_sample.clone();
+        let first = process(&mut per_sample, &input[..500], None);
+        assert_same(&first, &process(&mut block, &input[..500], Some(64)));
+        per_sample.cutoff = 5000.0;
+        block.cutoff = 5000.0;
+        assert_same(
+            &process(&mut per_sample, &input[500..], None),
+            &process(&mut block, &input[500..], Some(64)),
+        );
+
+        //patches are rendered in blocks
+        let synthesizer = || {
+            let mut synthesizer = BasicSynthesizer::new(
+                SawGenerator::new(110.0),
+                AdsrGenerator::new(0.01, 0.01, 0.5, 0.01, 0.01),
+                0.5,
+            );
+            synthesizer.play(0.01);
+            synthesizer
+        };
+        let mut mixer = Mixer::new();
+        let track = mixer.add_track(synthesizer());
+        mixer.track_mut(track).unwrap().add_insert(Biquad::low_pass(2000.0, 1.0));
+        mixer.track_mut(track).unwrap().panner.pan = 0.5;
+        let bus = mixer.add_bus();
+        mixer.bus_mut(bus).unwrap().add_effect(Delay::new(0.01, 0.3));
+        mixer.track_mut(track).unwrap().set_send(bus, -6.0);
+        let mut per_sample_output = Vec::new();
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut per_sample = synthesizer();
+        for _ in 0..2000 {
+            per_sample_output.push(per_sample.next_sample(&sample_timing));
+            sample_timing.tick();
+        }
+        assert_same(
+            &per_sample_output,
+            &render_to_buffer(&mut synthesizer(), SAMPLE_RATE, 2000.0 / SAMPLE_RATE),
+        );
+
+        //scheduled notes start within a block at the same sample as when played per sample
+        let silent_synthesizer = || {
+            BasicSynthesizer::new(
+                SawGenerator::new(110.0),
+                AdsrGenerator::new(0.001, 0.001, 0.5, 0.001, 0.001),
+                0.5,
+            )
+        };
+        let mut per_sample = silent_synthesizer();
+        let mut per_sample_output = Vec::new();
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        for clock in 0..1000 {
+            match clock {
+                150 => per_sample.note_on(),
+                230 => per_sample.note_off(),
+                300 => per_sample.play(0.005),
+                _ => {}
+            }
+            per_sample_output.push(per_sample.next_sample(&sample_timing));
+            sample_timing.tick();
+        }
+        let scheduled_synthesizer = || {
+            let mut synthesizer = silent_synthesizer();
+            synthesizer.play_at(300, 0.005);
+            synthesizer.note_off_at(230);
+            synthesizer.note_on_at(150);
+            synthesizer
+        };
+        let mut block = scheduled_synthesizer();
+        let mut block_output = vec![poly_sample!(); 1000];
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        for output in block_output.chunks_mut(64) {
+            block.next_block(&sample_timing, output);
+            sample_timing.clock += output.len();
+        }
+        assert_eq!(block.start_tick, 300);
+        assert_abs_diff_eq!(peak(&per_sample_output[..150], 0), 0.0);
+        assert!(peak(&per_sample_output[150..230], 0) > 0.0);
+        assert_same(&per_sample_output, &block_output);
+        assert_same(
+            &per_sample_output,
+            &render_to_buffer(&mut scheduled_synthesizer(), SAMPLE_RATE, 1000.0 / SAMPLE_RATE),
+        );
+
+        let mixed = render_to_buffer(&mut mixer, SAMPLE_RATE, 0.1);
+        assert_eq!(mixed.len(), 4800);
+        assert!(peak(&mixed[..2400], 1) > peak(&mixed[..2400], 0));
+        //the delay keeps ringing after the note
+        assert!(peak(&mixed[2400..], 0) > 0.0);
+    }
+
+    #[test]
+    fn graph() {
+        struct Gain(f32);
+
+        impl Node for Gain {
+            fn inputs(&self) -> Vec<Port> {
+                vec![Port::audio("in")]
+            }
+
+            fn outputs(&self) -> Vec<Port> {
+                vec![Port::audio("out")]
+            }
+
+            fn process_block(
+                &mut self,
+                _sample_timing: &SampleTiming,
+                inputs: &[Vec<PolySample>],
+                outputs: &mut [Vec<PolySample>],
+            ) {
+                for (output, input) in outputs[0].iter_mut().zip(&inputs[0]) {
+                    *output = input * self.0;
+                }
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+                self
+            }
+        }
+
+        //fan-out and summing
+        let mut graph = Graph::new();
+        let sine = graph.add_node(GeneratorNode::new(SineGenerator::new(440.0)));
+        let left = graph.add_node(Gain(0.25));
+        let right = graph.add_node(Gain(0.5));
+        let sum = graph.add_node(EffectNode::new(Biquad::low_pass(20000.0, FRAC_1_SQRT_2)));
+        graph.connect(sine, "out", left, "in").unwrap();
+        graph.connect(sine, "out", right, "in").unwrap();
+        graph.connect(left, "out", sum, "in").unwrap();
+        graph.connect(right, "out", sum, "in").unwrap();
+        graph.set_output(sum, "out").unwrap();
+        assert_eq!(
+            graph.connect(sine, "frequency", sum, "in"),
+            Err(GraphError::UnknownPort("frequency".into()))
+        );
+        assert_eq!(
+            graph.connect(sine, "out", sine, "frequency"),
+            Err(GraphError::PortKindMismatch(PortKind::Audio, PortKind::Control))
+        );
+        assert_eq!(graph.connect(sum, "out", left, "in"), Err(GraphError::Cycle));
+        let buffer = render_to_buffer(&mut graph, SAMPLE_RATE, 0.1);
+        assert_abs_diff_eq!(peak(&buffer[2400..], 0), 0.75, epsilon = 0.01);
+
+        //changing nodes in place
+        graph.node_mut_as::<Gain>(left).unwrap().0 = 0.75;
+        assert!(graph.node_mut_as::<Gain>(sine).is_none());
+        let buffer = render_to_buffer(&mut graph, SAMPLE_RATE, 0.1);
+        assert_abs_diff_eq!(peak(&buffer[2400..], 0), 1.25, epsilon = 0.01);
+        let node = graph.node_mut(sum).unwrap().as_any_mut();
+        node.downcast_mut::<EffectNode<Biquad>>().unwrap().effect.cutoff = 100.0;
+        let buffer = render_to_buffer(&mut graph, SAMPLE_RATE, 0.1);
+        assert!(peak(&buffer[2400..], 0) < 0.5);
+        graph.node_mut_as::<Gain>(left).unwrap().0 = 0.25;
+        graph.node_mut_as::<EffectNode<Biquad>>(sum).unwrap().effect.cutoff = 20000.0;
+
+        //removing nodes at runtime
+        graph.remove_node(right);
+        assert_eq!(graph.node_ids().count(), 3);
+        let buffer = render_to_buffer(&mut graph, SAMPLE_RATE, 0.1);
+        assert_abs_diff_eq!(peak(&buffer[2400..], 0), 0.25, epsilon = 0.01);
+        assert!(graph.disconnect(left, "out", sum, "in"));
+        let buffer = render_to_buffer(&mut graph, SAMPLE_RATE, 0.1);
+        //only the filter rings out
+        assert_abs_diff_eq!(peak(&buffer[100..], 0), 0.0, epsilon = 0.0001);
+
+        //control inputs
+        let mut graph = Graph::new();
+        let modulator =
+            graph.add_node(GeneratorNode::control(|_: &SampleTiming| poly_sample!([440.0])));
+        let sine = graph.add_node(GeneratorNode::new(SineGenerator::new(440.0)));
+        graph.connect(modulator, "out", sine, "frequency").unwrap();
+        graph.set_output(sine, "out").unwrap();
+        let buffer = render_to_buffer(&mut graph, SAMPLE_RATE, 1.0);
+        assert_abs_diff_eq!(zero_crossing_frequency(&buffer, 0), 880.0, epsilon = 1.0);
+
+        //feedback is delayed by one block
+        let mut graph = Graph::new();
+        let impulse = graph.add_node(GeneratorNode::new(|sample_timing: &SampleTiming| {
+            poly_sample!([if sample_timing.clock == 0 { 1.0 } else { 0.0 }])
+        }));
+        let sum = graph.add_node(Gain(1.0));
+        let feedback = graph.add_node(Gain(0.5));
+        graph.connect(impulse, "out", sum, "in").unwrap();
+        graph.connect(feedback, "out", sum, "in").unwrap();
+        graph.connect_feedback(sum, "out", feedback, "in").unwrap();
+        graph.set_output(sum, "out").unwrap();
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut buffer = vec![poly_sample!(); 16];
+        for block in buffer.chunks_mut(4) {
+            graph.next_block(&sample_timing, block);
+            sample_timing.clock += block.len();
+        }
+        let impulses: Vec<f32> = buffer.iter().map(|poly_sample| poly_sample[0]).collect();
+        let expected =
+            [1.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.25, 0.0, 0.0, 0.0, 0.125, 0.0, 0.0, 0.0];
+        assert_abs_diff_eq!(impulses[..], expected[..]);
+    }
+
+    #[test]
+    fn modulation() {
+        let sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let values = |lfo: &mut LfoGenerator, count: usize| {
+            let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+            (0..count)
+                .map(|_| {
+                    let value = lfo.generate(&sample_timing)[0];
+                    sample_timing.tick();
+                    value
+                })
+                .collect::<Vec<_>>()
+        };
+
+        //shapes
+        let mut lfo = LfoGenerator::new(LfoShape::Sine, 1.0);
+        lfo.phase_offset = 0.25;
+        assert_abs_diff_eq!(values(&mut lfo, 1)[0], 1.0);
+        let mut lfo = LfoGenerator::new(LfoShape::Triangle, 1.0);
+        let triangle = values(&mut lfo, 48000);
+        assert_abs_diff_eq!(triangle[12000], 1.0, epsilon = 0.01);
+        assert_abs_diff_eq!(triangle[36000], -1.0, epsilon = 0.01);
+        let mut lfo = LfoGenerator::tempo_synced(LfoShape::Square, 120.0, 0.25);
+        assert_abs_diff_eq!(lfo.frequency, 2.0);
+        lfo.unipolar = true;
+        let square = values(&mut lfo, 24000);
+        assert_abs_diff_eq!(square[..12000], [1.0; 12000][..]);
+        assert!(square[12000..].iter().all(|value| *value == 0.0 || *value == 1.0));
+        let mut lfo = LfoGenerator::new(LfoShape::SampleAndHold, 10.0);
+        let held = values(&mut lfo, 9600);
+        assert!(held[..4790].iter().all(|value| *value == held[0]));
+        assert_ne!(held[4810], held[0]);
+        lfo.reset();
+        assert_abs_diff_eq!(values(&mut lfo, 1)[0], held[0]);
+        //the seed applies without a reset
+        let mut seeded = LfoGenerator::new(LfoShape::SampleAndHold, 10.0);
+        seeded.seed = 7;
+        let seeded_held = values(&mut seeded, 9600);
+        assert_ne!(seeded_held[0], held[0]);
+        seeded.reset();
+        assert_abs_diff_eq!(values(&mut seeded, 9600)[..], seeded_held[..]);
+
+        //params and control values
+        let control = ControlValue::default();
+        let mut param = Param::with_range(1000.0, 0.0, 1200.0);
+        param.add_modulation(ModSource::generator(LfoGenerator::new(LfoShape::Square, 1.0)), 500.0);
+        param.add_modulation(ModSource::control(control.clone()), 100.0);
+        assert_abs_diff_eq!(param.next_value(&sample_timing), 1200.0);
+        param.reset();
+        control.set_midi(127);
+        assert_abs_diff_eq!(param.base, 1000.0);
+        param.modulations[0].1 = 100.0;
+        assert_abs_diff_eq!(param.next_value(&sample_timing), 1200.0);
+        control.set(-20.0);
+        assert_abs_diff_eq!(param.next_value(&sample_timing), 0.0);
+        //reversed bounds don't panic, NaN bounds are ignored
+        assert_abs_diff_eq!(Param::with_range(0.5, 1.0, 0.0).next_value(&sample_timing), 0.0);
+        assert_abs_diff_eq!(Param::with_range(0.5, f32::NAN, 0.2).next_value(&sample_timing), 0.2);
+        assert_abs_diff_eq!(Param::with_range(0.5, 0.7, f32::NAN).next_value(&sample_timing), 0.7);
+
+        //vibrato of an octave on a synthesizer
+        let mut synthesizer = BasicSynthesizer::new(
+            SineGenerator::new(440.0),
+            AdsrGenerator::new(0.0, 0.0, 1.0, 1.0, 0.0),
+            1.0,
+        );
+        let lfo = synthesizer
+            .mod_matrix
+            .add_source(ModSource::generator(LfoGenerator::new(LfoShape::Square, 1.0)));
+        synthesizer.mod_matrix.route(lfo, SynthDestination::Pitch, 12.0);
+        synthesizer.mod_matrix.route(lfo, SynthDestination::Volume, -0.25);
+        synthesizer.play(1.0);
+        let buffer = render_to_buffer(&mut synthesizer, SAMPLE_RATE, 1.0);
+        assert_abs_diff_eq!(zero_crossing_frequency(&buffer[..24000], 0), 880.0, epsilon = 2.0);
+        assert_abs_diff_eq!(zero_crossing_frequency(&buffer[24000..], 0), 220.0, epsilon = 2.0);
+        assert_abs_diff_eq!(peak(&buffer[..24000], 0), 0.75, epsilon = 0.01);
+        assert_abs_diff_eq!(peak(&buffer[24000..], 0), 1.25, epsilon = 0.01);
+        assert_abs_diff_eq!(synthesizer.base_generator.frequency, 440.0);
+
+        //effect parameters
+        let mut panner = Modulated::new(Panner::new(0.0, PanLaw::ConstantPower));
+        let pan =
+            panner.add_param(Param::with_range(0.0, -1.0, 1.0), |panner, pan| panner.pan = pan);
+        let control = ControlValue::new(0.5);
+        let source = panner.mod_matrix.add_source(ModSource::control(control.clone()));
+        panner.mod_matrix.route(source, pan, 4.0);
+        let output = panner.process(&sample_timing, poly_sample!([1.0]));
+        assert_abs_diff_eq!(panner.inner.pan, 1.0);
+        assert_abs_diff_eq!(output[0], 0.0);
+        control.set(-0.125);
+        panner.process(&sample_timing, poly_sample!([1.0]));
+        assert_abs_diff_eq!(panner.inner.pan, -0.5);
+
+        //generator parameters
+        let mut sine = Modulated::new(SineGenerator::new(440.0));
+        let frequency = sine.add_param(Param::new(440.0), |sine, frequency| {
+            sine.frequency = frequency;
+        });
+        let control = ControlValue::new(1.0);
+        let source = sine.mod_matrix.add_source(ModSource::control(control));
+        sine.mod_matrix.route(source, frequency, 440.0);
+        let mut sample_timing = SampleTiming::new(SAMPLE_RATE);
+        let mut buffer = vec![poly_sample!(); SAMPLE_RATE as usize];
+        for poly_sample in &mut buffer {
+            *poly_sample = sine.generate(&sample_timing);
+            sample_timing.tick();
+        }
+        assert_abs_diff_eq!(sine.inner.frequency, 880.0);
+        assert_abs_diff_eq!(zero_crossing_frequency(&buffer, 0), 880.0, epsilon = 1.0);
     }
 }
@@ -0,0 +1,98 @@
+mod mod_matrix;
+mod modulated;
+mod param;
+
+use crate::{generator::Generator, SampleTiming};
+pub use mod_matrix::{ModMatrix, SourceId};
+pub use modulated::Modulated;
+pub use param::Param;
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
+trait CloneGenerator: Generator {
+    fn clone_box(&self) -> Box<dyn CloneGenerator>;
+}
+
+impl<G: 'static + Generator + Clone> CloneGenerator for G {
+    fn clone_box(&self) -> Box<dyn CloneGenerator> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn CloneGenerator> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Value shared between threads, e.g. set by a MIDI CC handler while a patch is playing.
+#[derive(Clone, Debug, Default)]
+pub struct ControlValue(Arc<AtomicU32>);
+
+impl ControlValue {
+    pub fn new(value: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(value.to_bits())))
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Sets the value of a MIDI CC from `0` to `127`, mapped to `0.0` to `1.0`.
+    pub fn set_midi(&self, value: u8) {
+        self.set(value.min(127) as f32 / 127.0);
+    }
+}
+
+#[derive(Clone)]
+enum Source {
+    Generator(Box<dyn CloneGenerator>),
+    Control(ControlValue),
+}
+
+/// Signal modulating parameters, e.g. an [`LfoGenerator`](crate::generator::LfoGenerator),
+/// an envelope or a [`ControlValue`].
+#[derive(Clone)]
+pub struct ModSource {
+    source: Source,
+    /// Restarts generators when [`reset`](Self::reset) is called, e.g. when a new note starts playing.
+    pub retrigger: bool,
+}
+
+impl ModSource {
+    /// Uses the first channel of `generator`.
+    pub fn generator<G: 'static + Generator + Clone>(generator: G) -> Self {
+        Self {
+            source: Source::Generator(Box::new(generator)),
+            retrigger: true,
+        }
+    }
+
+    pub fn control(control: ControlValue) -> Self {
+        Self {
+            source: Source::Control(control),
+            retrigger: false,
+        }
+    }
+
+    pub fn next_value(&mut self, sample_timing: &SampleTiming) -> f32 {
+        match &mut self.source {
+            Source::Generator(generator) => {
+                generator.generate(sample_timing).first().copied().unwrap_or_default()
+            }
+            Source::Control(control) => control.get(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        if let (Source::Generator(generator), true) = (&mut self.source, self.retrigger) {
+            generator.reset();
+        }
+    }
+}
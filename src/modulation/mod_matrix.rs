@@ -0,0 +1,80 @@
+use super::ModSource;
+use crate::SampleTiming;
+
+/// Handle of a source in a [`ModMatrix`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SourceId(usize);
+
+/// Routes shared modulation sources to destinations `D` with depths, e.g. one LFO to pitch and volume.
+#[derive(Clone)]
+pub struct ModMatrix<D> {
+    sources: Vec<ModSource>,
+    routes: Vec<(SourceId, D, f32)>,
+    values: Vec<f32>,
+}
+
+impl<D: Copy + PartialEq> ModMatrix<D> {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            routes: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    pub fn add_source(&mut self, source: ModSource) -> SourceId {
+        self.sources.push(source);
+        self.values.push(0.0);
+        SourceId(self.sources.len() - 1)
+    }
+
+    pub fn source_mut(&mut self, id: SourceId) -> Option<&mut ModSource> {
+        self.sources.get_mut(id.0)
+    }
+
+    /// Routes `source` to `destination` with `depth`, replacing a previous route between them.
+    pub fn route(&mut self, source: SourceId, destination: D, depth: f32) {
+        self.unroute(source, destination);
+        self.routes.push((source, destination, depth));
+    }
+
+    pub fn unroute(&mut self, source: SourceId, destination: D) {
+        self.routes.retain(|(route_source, route_destination, _)| {
+            *route_source != source || *route_destination != destination
+        });
+    }
+
+    /// Returns `true` if nothing is routed.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Advances all sources by one sample.
+    pub fn process(&mut self, sample_timing: &SampleTiming) {
+        for (source, value) in self.sources.iter_mut().zip(&mut self.values) {
+            *value = source.next_value(sample_timing);
+        }
+    }
+
+    /// Sum of the sources routed to `destination` scaled by their depths, as of the last [`process`](Self::process).
+    pub fn amount(&self, destination: D) -> f32 {
+        self.routes
+            .iter()
+            .filter(|(_, route_destination, _)| *route_destination == destination)
+            .map(|(source, _, depth)| self.values[source.0] * depth)
+            .sum()
+    }
+
+    /// Restarts all retriggered sources.
+    pub fn reset(&mut self) {
+        for source in &mut self.sources {
+            source.reset();
+        }
+    }
+}
+
+impl<D: Copy + PartialEq> Default for ModMatrix<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,80 @@
+use super::{ModMatrix, Param};
+use crate::{effect::Effect, generator::Generator, PolySample, SampleTiming};
+
+type Setter<T> = fn(&mut T, f32);
+
+/// Modulates parameters of an [`Effect`] or a [`Generator`], each a [`Param`] applied by a setter before every sample.
+///
+/// Destinations of the [`mod_matrix`](Self::mod_matrix) are the indices returned by [`add_param`](Self::add_param).
+#[derive(Clone)]
+pub struct Modulated<T> {
+    pub inner: T,
+    pub mod_matrix: ModMatrix<usize>,
+    params: Vec<(Param, Setter<T>)>,
+}
+
+impl<T> Modulated<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            mod_matrix: ModMatrix::new(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Adds a modulated parameter, e.g. `|filter: &mut Biquad, cutoff| filter.cutoff = cutoff`.
+    ///
+    /// The value set by `setter` overrides changes of the parameter made directly on the inner effect or generator.
+    pub fn add_param(&mut self, param: Param, setter: Setter<T>) -> usize {
+        self.params.push((param, setter));
+        self.params.len() - 1
+    }
+
+    pub fn param_mut(&mut self, index: usize) -> Option<&mut Param> {
+        self.params.get_mut(index).map(|(param, _)| param)
+    }
+
+    /// Restarts all retriggered sources.
+    pub fn reset(&mut self) {
+        self.mod_matrix.reset();
+        for (param, _) in &mut self.params {
+            param.reset();
+        }
+    }
+
+    fn apply_params(&mut self, sample_timing: &SampleTiming) {
+        self.mod_matrix.process(sample_timing);
+        for (index, (param, setter)) in self.params.iter_mut().enumerate() {
+            let value = param.next_value_with_offset(sample_timing, self.mod_matrix.amount(index));
+            setter(&mut self.inner, value);
+        }
+    }
+}
+
+impl<E: Effect> Effect for Modulated<E> {
+    fn process(&mut self, sample_timing: &SampleTiming, poly_sample: PolySample) -> PolySample {
+        self.apply_params(sample_timing);
+        self.inner.process(sample_timing, poly_sample)
+    }
+}
+
+impl<G: Generator> Generator for Modulated<G> {
+    fn generate(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        self.apply_params(sample_timing);
+        self.inner.generate(sample_timing)
+    }
+
+    /// Resets the inner generator and restarts all retriggered sources.
+    fn reset(&mut self) {
+        Modulated::reset(self);
+        self.inner.reset();
+    }
+
+    fn frequency(&self) -> Option<f32> {
+        self.inner.frequency()
+    }
+
+    fn set_frequency(&mut self, frequency: f32) {
+        self.inner.set_frequency(frequency);
+    }
+}
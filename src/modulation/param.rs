@@ -0,0 +1,60 @@
+use super::ModSource;
+use crate::SampleTiming;
+
+/// Parameter value made of a `base` and the sum of its modulation sources scaled by their depths.
+#[derive(Clone)]
+pub struct Param {
+    pub base: f32,
+    /// Lower bound of the modulated value, ignored if NaN.
+    pub min: f32,
+    /// Upper bound of the modulated value, wins over `min` if smaller, ignored if NaN.
+    pub max: f32,
+    /// Sources with the depth they are added with.
+    pub modulations: Vec<(ModSource, f32)>,
+}
+
+impl Param {
+    pub fn new(base: f32) -> Self {
+        Self::with_range(base, f32::NEG_INFINITY, f32::INFINITY)
+    }
+
+    pub fn with_range(base: f32, min: f32, max: f32) -> Self {
+        Self {
+            base,
+            min,
+            max,
+            modulations: Vec::new(),
+        }
+    }
+
+    pub fn add_modulation(&mut self, source: ModSource, depth: f32) {
+        self.modulations.push((source, depth));
+    }
+
+    /// Advances all sources by one sample and returns the modulated value.
+    pub fn next_value(&mut self, sample_timing: &SampleTiming) -> f32 {
+        self.next_value_with_offset(sample_timing, 0.0)
+    }
+
+    /// Like [`next_value`](Self::next_value), with `offset` added to the base, e.g. from a [`ModMatrix`](super::ModMatrix).
+    pub fn next_value_with_offset(&mut self, sample_timing: &SampleTiming, offset: f32) -> f32 {
+        let modulation: f32 = self
+            .modulations
+            .iter_mut()
+            .map(|(source, depth)| source.next_value(sample_timing) * *depth)
+            .sum();
+        (self.base + offset + modulation).max(self.min).min(self.max)
+    }
+
+    pub fn reset(&mut self) {
+        for (source, _) in &mut self.modulations {
+            source.reset();
+        }
+    }
+}
+
+impl Default for Param {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
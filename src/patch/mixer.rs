@@ -0,0 +1,279 @@
+use crate::{
+    effect::{from_db, Effect, Panner},
+    prelude::*,
+};
+
+/// Handle of a [`Track`] in a [`Mixer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TrackId(usize);
+
+/// Handle of a [`MixerBus`] in a [`Mixer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BusId(usize);
+
+/// Channel strip of a [`Mixer`], processing a patch through inserts, gain and pan.
+pub struct Track {
+    patch: Box<dyn Patch>,
+    /// Effects the patch output is processed by, in order.
+    pub inserts: Vec<Box<dyn Effect>>,
+    /// Gain in dB.
+    pub gain: f32,
+    /// Pans mono patches, balances stereo patches.
+    pub panner: Panner,
+    pub muted: bool,
+    /// While any track is soloed, only soloed tracks are heard.
+    pub soloed: bool,
+    /// Levels in dB the track is sent to buses with, after gain and pan.
+    pub sends: Vec<(BusId, f32)>,
+    finished: bool,
+}
+
+impl Track {
+    fn new(patch: Box<dyn Patch>) -> Self {
+        Self {
+            patch,
+            inserts: Vec::new(),
+            gain: 0.0,
+            panner: Panner {
+                balance: true,
+                ..Panner::default()
+            },
+            muted: false,
+            soloed: false,
+            sends: Vec::new(),
+            finished: false,
+        }
+    }
+
+    pub fn add_insert<E: 'static + Effect>(&mut self, effect: E) {
+        self.inserts.push(Box::new(effect));
+    }
+
+    /// Sends the track to `bus` with `level` in dB, replacing a previous send to the same bus.
+    pub fn set_send(&mut self, bus: BusId, level: f32) {
+        self.remove_send(bus);
+        self.sends.push((bus, level));
+    }
+
+    pub fn remove_send(&mut self, bus: BusId) {
+        self.sends.retain(|(send_bus, _)| *send_bus != bus);
+    }
+}
+
+/// Return bus of a [`Mixer`], processing the sum of all sends through shared effects, e.g. one reverb for all tracks.
+pub struct MixerBus {
+    pub effects: Vec<Box<dyn Effect>>,
+    /// Gain in dB.
+    pub gain: f32,
+    pub muted: bool,
+    input: Vec<PolySample>,
+}
+
+impl MixerBus {
+    pub fn add_effect<E: 'static + Effect>(&mut self, effect: E) {
+        self.effects.push(Box::new(effect));
+    }
+}
+
+/// Mixes patches as tracks with gain, pan, mute, solo, insert effects and sends to return buses.
+///
+/// Finished tracks and empty samples are treated as silence while other tracks keep playing.
+/// A patch is not called anymore after it finished, but the inserts of its track keep ringing while the mixer plays.
+/// The mixer is finished when all of its tracks are, or when it has no tracks.
+#[derive(Default)]
+pub struct Mixer {
+    tracks: Vec<(TrackId, Track)>,
+    buses: Vec<(BusId, MixerBus)>,
+    /// Gain in dB of the mixed output.
+    pub gain: f32,
+    next_id: usize,
+    buffer: Vec<PolySample>,
+}
+
+impl Mixer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_track<P: 'static + Patch>(&mut self, patch: P) -> TrackId {
+        let id = TrackId(self.next_id());
+        self.tracks.push((id, Track::new(Box::new(patch))));
+        id
+    }
+
+    pub fn track(&self, id: TrackId) -> Option<&Track> {
+        self.tracks.iter().find(|(track_id, _)| *track_id == id).map(|(_, track)| track)
+    }
+
+    pub fn track_mut(&mut self, id: TrackId) -> Option<&mut Track> {
+        self.tracks.iter_mut().find(|(track_id, _)| *track_id == id).map(|(_, track)| track)
+    }
+
+    pub fn track_ids(&self) -> impl Iterator<Item = TrackId> + '_ {
+        self.tracks.iter().map(|(id, _)| *id)
+    }
+
+    pub fn remove_track(&mut self, id: TrackId) -> Option<Track> {
+        let index = self.tracks.iter().position(|(track_id, _)| *track_id == id)?;
+        Some(self.tracks.remove(index).1)
+    }
+
+    /// Replaces the patch of a track, keeping its settings. Returns the previous patch.
+    pub fn replace_patch<P: 'static + Patch>(
+        &mut self,
+        id: TrackId,
+        patch: P,
+    ) -> Option<Box<dyn Patch>> {
+        let track = self.track_mut(id)?;
+        track.finished = false;
+        Some(std::mem::replace(&mut track.patch, Box::new(patch)))
+    }
+
+    pub fn add_bus(&mut self) -> BusId {
+        let id = BusId(self.next_id());
+        let bus = MixerBus {
+            effects: Vec::new(),
+            gain: 0.0,
+            muted: false,
+            input: Vec::new(),
+        };
+        self.buses.push((id, bus));
+        id
+    }
+
+    pub fn bus_mut(&mut self, id: BusId) -> Option<&mut MixerBus> {
+        self.buses.iter_mut().find(|(bus_id, _)| *bus_id == id).map(|(_, bus)| bus)
+    }
+
+    /// Removes a bus and all sends to it.
+    pub fn remove_bus(&mut self, id: BusId) -> Option<MixerBus> {
+        let index = self.buses.iter().position(|(bus_id, _)| *bus_id == id)?;
+        for (_, track) in &mut self.tracks {
+            track.remove_send(id);
+        }
+        Some(self.buses.remove(index).1)
+    }
+
+    fn next_id(&mut self) -> usize {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+impl Patch for Mixer {
+    fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        let mut output = [poly_sample!()];
+        self.next_block(sample_timing, &mut output);
+        let [poly_sample] = output;
+        poly_sample
+    }
+
+    fn next_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) -> usize {
+        if self.state() == PatchState::Finished {
+            return 0;
+        }
+        let Mixer {
+            tracks,
+            buses,
+            buffer,
+            ..
+        } = self;
+        let len = output.len();
+        for poly_sample in output.iter_mut() {
+            *poly_sample = poly_sample!();
+        }
+        for (_, bus) in buses.iter_mut() {
+            bus.input.clear();
+            bus.input.resize(len, poly_sample!());
+        }
+        if buffer.len() < len {
+            buffer.resize(len, poly_sample!());
+        }
+        let buffer = &mut buffer[..len];
+        let any_soloed = tracks.iter().any(|(_, track)| track.soloed);
+
+        for (_, track) in tracks.iter_mut() {
+            let written = if track.finished || track.patch.state() == PatchState::Finished {
+                track.finished = true;
+                0
+            } else {
+                track.patch.next_block(sample_timing, buffer)
+            };
+            for (i, poly_sample) in buffer.iter_mut().enumerate() {
+                if i >= written || poly_sample.is_empty() {
+                    //an empty sample ends the patch, like in a `MasterPatch`
+                    track.finished = true;
+                    *poly_sample = poly_sample!([0.0]);
+                }
+            }
+            for effect in &mut track.inserts {
+                effect.process_block(sample_timing, buffer);
+            }
+            let gain = from_db(track.gain);
+            for poly_sample in buffer.iter_mut() {
+                *poly_sample *= gain;
+            }
+            track.panner.process_block(sample_timing, buffer);
+            //tracks and their inserts keep running while muted, so they stay in time
+            if track.muted || (any_soloed && !track.soloed) {
+                continue;
+            }
+
+            for (bus_id, level) in &track.sends {
+                if let Some((_, bus)) = buses.iter_mut().find(|(id, _)| id == bus_id) {
+                    let level = from_db(*level);
+                    for (input, poly_sample) in bus.input.iter_mut().zip(buffer.iter()) {
+                        input.mix(&(poly_sample * level));
+                    }
+                }
+            }
+            for (master, poly_sample) in output.iter_mut().zip(buffer.iter()) {
+                master.mix(poly_sample);
+            }
+        }
+
+        for (_, bus) in buses.iter_mut() {
+            //effects like reverbs keep ringing without input
+            for poly_sample in bus.input.iter_mut().filter(|poly_sample| poly_sample.is_empty()) {
+                poly_sample.push(0.0);
+            }
+            for effect in &mut bus.effects {
+                effect.process_block(sample_timing, &mut bus.input);
+            }
+            if !bus.muted {
+                let gain = from_db(bus.gain);
+                for (master, poly_sample) in output.iter_mut().zip(&bus.input) {
+                    master.mix(&(poly_sample * gain));
+                }
+            }
+        }
+
+        let gain = from_db(self.gain);
+        for poly_sample in output.iter_mut() {
+            if poly_sample.is_empty() {
+                poly_sample.push(0.0);
+            }
+            *poly_sample *= gain;
+        }
+        len
+    }
+
+    fn state(&self) -> PatchState {
+        let states = self.tracks.iter().map(|(_, track)| {
+            if track.finished {
+                PatchState::Finished
+            } else {
+                track.patch.state()
+            }
+        });
+        if states.clone().all(|state| state == PatchState::Finished) {
+            PatchState::Finished
+        } else if states.clone().all(|state| state != PatchState::Running) {
+            PatchState::Silent
+        } else {
+            PatchState::Running
+        }
+    }
+}
+
+impl OutPatch for Mixer {}
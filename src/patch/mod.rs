@@ -0,0 +1,184 @@
+mod mixer;
+
+use crate::{cpal::CpalEvent, prelude::*};
+pub use mixer::{BusId, Mixer, MixerBus, Track, TrackId};
+
+/// Lifecycle of a [`Patch`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatchState {
+    /// Producing sound.
+    Running,
+    /// Still running, but currently producing silence, e.g. a synthesizer without playing notes.
+    Silent,
+    /// Ended, [`next_sample`](Patch::next_sample) shouldn't be called anymore.
+    Finished,
+}
+
+pub trait Patch: Send {
+    fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample;
+
+    /// Fills `output` with consecutive samples, starting at `sample_timing`.
+    ///
+    /// Returns the count of written samples, which is less than `output.len()` if the patch finished during the block.
+    /// An empty sample finishes the patch as well, it isn't counted as written.
+    fn next_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) -> usize {
+        let mut sample_timing = *sample_timing;
+        for (i, poly_sample) in output.iter_mut().enumerate() {
+            if self.state() == PatchState::Finished {
+                return i;
+            }
+            *poly_sample = self.next_sample(&sample_timing);
+            if poly_sample.is_empty() {
+                return i;
+            }
+            sample_timing.tick();
+        }
+        output.len()
+    }
+
+    /// State after the last call of [`next_sample`](Self::next_sample) or [`next_block`](Self::next_block).
+    fn state(&self) -> PatchState {
+        PatchState::Running
+    }
+}
+
+/// Count of frames patches are rendered in at once,
+/// by [`OutPatch::write_data`] and [`render_to_buffer`](crate::render::render_to_buffer).
+pub const BLOCK_SIZE: usize = 64;
+
+pub trait OutPatch: Patch {
+    /// Fills `output` with interleaved frames of `channels`, until the patch is [`Finished`](PatchState::Finished).
+    fn write_data<T: cpal::Sample>(
+        &mut self,
+        output: &mut [T],
+        channels: usize,
+        sample_timing: &mut SampleTiming,
+    ) -> Option<CpalEvent> {
+        let mut block: [PolySample; BLOCK_SIZE] = std::array::from_fn(|_| poly_sample!());
+        for frames in output.chunks_mut(BLOCK_SIZE * channels) {
+            let frame_count = frames.len() / channels;
+            let written = self.next_block(sample_timing, &mut block[..frame_count]);
+
+            for (i, frame) in frames.chunks_mut(channels).enumerate() {
+                let next_sample = if i < written { &block[i][..] } else { &[] };
+                let mut next_samples = next_sample
+                    .iter()
+                    .chain(std::iter::repeat(&0.0))
+                    .map(|s| cpal::Sample::from(s));
+                for sample in frame.iter_mut() {
+                    *sample = next_samples.next().unwrap();
+                }
+            }
+            sample_timing.clock += written;
+
+            if written < frame_count {
+                return Some(CpalEvent::Exit);
+            }
+        }
+        None
+    }
+}
+
+/// Sums multiple patches, removing them once they are finished.
+///
+/// Patches returning an empty [`PolySample`] are treated as finished as well.
+/// The master patch is finished when all of its patches are, or after [`stop`](Self::stop).
+#[derive(Default)]
+pub struct MasterPatch {
+    patches: Vec<Box<dyn Patch>>,
+    stopped: bool,
+    buffer: Vec<PolySample>,
+}
+
+impl MasterPatch {
+    pub fn new() -> Self {
+        MasterPatch {
+            patches: Vec::new(),
+            stopped: false,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn add_patch<P: 'static + Patch>(&mut self, patch: P) {
+        self.patches.push(Box::new(patch));
+    }
+
+    /// Count of patches that haven't finished yet.
+    pub fn patch_count(&self) -> usize {
+        self.patches.len()
+    }
+
+    /// Finishes the master patch, regardless of the state of its patches.
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+}
+
+impl Patch for MasterPatch {
+    fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        let mut master = poly_sample!();
+        self.patches.retain_mut(|patch| {
+            if patch.state() == PatchState::Finished {
+                return false;
+            }
+            let patch_sample = patch.next_sample(sample_timing);
+            master.mix(&patch_sample);
+            !patch_sample.is_empty()
+        });
+        if master.is_empty() {
+            master.push(0.0);
+        }
+        master
+    }
+
+    fn next_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) -> usize {
+        if self.state() == PatchState::Finished {
+            return 0;
+        }
+        for poly_sample in output.iter_mut() {
+            *poly_sample = poly_sample!();
+        }
+        if self.buffer.len() < output.len() {
+            self.buffer.resize(output.len(), poly_sample!());
+        }
+        let buffer = &mut self.buffer[..output.len()];
+
+        //frames until the last patch finished
+        let mut written = 0;
+        self.patches.retain_mut(|patch| {
+            if patch.state() == PatchState::Finished {
+                return false;
+            }
+            let patch_written = patch.next_block(sample_timing, buffer);
+            for (master, patch_sample) in output.iter_mut().zip(&buffer[..patch_written]) {
+                master.mix(patch_sample);
+            }
+            let finished = patch_written < buffer.len();
+            written = written.max(if finished { patch_written } else { buffer.len() });
+            !finished
+        });
+        if !self.patches.is_empty() {
+            written = output.len();
+        }
+
+        for poly_sample in &mut output[..written] {
+            if poly_sample.is_empty() {
+                poly_sample.push(0.0);
+            }
+        }
+        written
+    }
+
+    fn state(&self) -> PatchState {
+        let states = self.patches.iter().map(|patch| patch.state());
+        if self.stopped || states.clone().all(|state| state == PatchState::Finished) {
+            PatchState::Finished
+        } else if states.clone().all(|state| state != PatchState::Running) {
+            PatchState::Silent
+        } else {
+            PatchState::Running
+        }
+    }
+}
+
+impl OutPatch for MasterPatch {}
@@ -94,6 +94,21 @@ impl MulAssign<f32> for PolySample {
     }
 }
 
+#[cfg(feature = "mono")]
+impl PolySample {
+    pub const MAX_CHANNELS: usize = 1;
+}
+
+#[cfg(feature = "stereo")]
+impl PolySample {
+    pub const MAX_CHANNELS: usize = 2;
+}
+
+#[cfg(feature = "unlimited")]
+impl PolySample {
+    pub const MAX_CHANNELS: usize = usize::MAX;
+}
+
 impl PolySample {
     /// Multiplies `self` by cycled `other`.
     /// Especially useful for volume effects.
@@ -103,6 +118,16 @@ impl PolySample {
         }
     }
 
+    /// Adds `other` channel by channel, taking over the channels `self` doesn't have.
+    pub fn mix(&mut self, other: &Self) {
+        for (i, sample) in other.iter().enumerate() {
+            match self.get_mut(i) {
+                None => self.push(*sample),
+                Some(current_sample) => *current_sample += sample,
+            }
+        }
+    }
+
     /// Clones all channels `n` times and concatenates them.
     pub fn polify(&mut self, n: usize) {
         for _ in 0..(n - 1) {
@@ -116,4 +141,22 @@ impl PolySample {
                 * ((to.end - to.start) / (from.end - from.start))
         }
     }
+
+    /// Converts the first two channels from left/right to mid/side, with mid as the average of both channels.
+    pub fn encode_mid_side(&mut self) {
+        if self.len() >= 2 {
+            let (left, right) = (self[0], self[1]);
+            self[0] = (left + right) / 2.0;
+            self[1] = (left - right) / 2.0;
+        }
+    }
+
+    /// Converts the first two channels from mid/side back to left/right.
+    pub fn decode_mid_side(&mut self) {
+        if self.len() >= 2 {
+            let (mid, side) = (self[0], self[1]);
+            self[0] = mid + side;
+            self[1] = mid - side;
+        }
+    }
 }
@@ -0,0 +1,135 @@
+use crate::{
+    cpal::CpalEvent,
+    patch::{OutPatch, Patch, BLOCK_SIZE},
+    poly_sample, PolySample, SampleTiming,
+};
+use anyhow::Result;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BitDepth {
+    Int16,
+    Int24,
+    Float32,
+}
+
+impl BitDepth {
+    fn bits_per_sample(self) -> u16 {
+        match self {
+            BitDepth::Int16 => 16,
+            BitDepth::Int24 => 24,
+            BitDepth::Float32 => 32,
+        }
+    }
+
+    fn sample_format(self) -> SampleFormat {
+        match self {
+            BitDepth::Int16 | BitDepth::Int24 => SampleFormat::Int,
+            BitDepth::Float32 => SampleFormat::Float,
+        }
+    }
+}
+
+/// Renders patches into WAV files as fast as possible, without needing an audio device.
+#[derive(Clone, Debug)]
+pub struct WavRenderer {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bit_depth: BitDepth,
+    /// Rendering stops after this duration (in seconds), even if the patch hasn't ended.
+    pub max_duration: Option<f32>,
+    /// Count of frames passed to [`OutPatch::write_data`] at once.
+    pub block_size: usize,
+}
+
+impl WavRenderer {
+    pub fn new(sample_rate: u32, channels: u16, bit_depth: BitDepth) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            bit_depth,
+            max_duration: None,
+            block_size: 512,
+        }
+    }
+
+    /// Renders `patch` into the WAV file at `path` until the patch is [`Finished`](crate::patch::PatchState::Finished)
+    /// or [`max_duration`](Self::max_duration) is reached.
+    ///
+    /// Returns the count of rendered frames.
+    pub fn render_patch<P: OutPatch, T: AsRef<Path>>(
+        &self,
+        patch: &mut P,
+        path: T,
+    ) -> Result<usize> {
+        let spec = WavSpec {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bits_per_sample: self.bit_depth.bits_per_sample(),
+            sample_format: self.bit_depth.sample_format(),
+        };
+        let mut writer = WavWriter::create(path, spec)?;
+
+        let channels = self.channels as usize;
+        let mut sample_timing = SampleTiming::new(self.sample_rate as f32);
+        let max_frames =
+            self.max_duration.map(|duration| sample_timing.duration_to_sample_count(duration));
+        let mut buffer = vec![0.0f32; self.block_size.max(1) * channels];
+
+        loop {
+            let mut block_frames = self.block_size.max(1);
+            if let Some(max_frames) = max_frames {
+                block_frames = block_frames.min(max_frames - sample_timing.clock);
+                if block_frames == 0 {
+                    break;
+                }
+            }
+            let block = &mut buffer[..block_frames * channels];
+
+            let start_clock = sample_timing.clock;
+            let event = patch.write_data(block, channels, &mut sample_timing);
+            let written_frames = sample_timing.clock - start_clock;
+
+            for &sample in &block[..written_frames * channels] {
+                let sample = sample.clamp(-1.0, 1.0);
+                match self.bit_depth {
+                    BitDepth::Int16 => writer.write_sample((sample * i16::MAX as f32) as i16)?,
+                    BitDepth::Int24 => writer.write_sample((sample * 8_388_607.0) as i32)?,
+                    BitDepth::Float32 => writer.write_sample(sample)?,
+                }
+            }
+
+            if let Some(CpalEvent::Exit) = event {
+                break;
+            }
+        }
+
+        writer.finalize()?;
+        Ok(sample_timing.clock)
+    }
+}
+
+/// Renders `patch` into memory until it is [`Finished`](crate::patch::PatchState::Finished) or `duration` (in seconds) is reached.
+///
+/// Useful for analyzing the output of patches, e.g. in tests.
+pub fn render_to_buffer<P: Patch>(
+    patch: &mut P,
+    sample_rate: f32,
+    duration: f32,
+) -> Vec<PolySample> {
+    let mut sample_timing = SampleTiming::new(sample_rate);
+    let sample_count = sample_timing.duration_to_sample_count(duration);
+    let mut buffer = vec![poly_sample!(); sample_count];
+
+    for block in buffer.chunks_mut(BLOCK_SIZE) {
+        let written = patch.next_block(&sample_timing, block);
+        sample_timing.clock += written;
+        if written < block.len() {
+            break;
+        }
+    }
+
+    buffer.truncate(sample_timing.clock);
+    buffer
+}
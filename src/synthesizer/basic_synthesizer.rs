@@ -1,7 +1,25 @@
 use crate::{
     generator::{AdsrGenerator, Generator},
+    modulation::ModMatrix,
     prelude::*,
 };
+use std::collections::VecDeque;
+
+/// Parameter of a [`BasicSynthesizer`] its [`ModMatrix`] can modulate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SynthDestination {
+    /// Offset in semitones.
+    Pitch,
+    /// Offset added to `volume`.
+    Volume,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NoteEvent {
+    Play(f32),
+    NoteOn,
+    NoteOff,
+}
 
 #[derive(Clone)]
 pub struct BasicSynthesizer<G: Generator> {
@@ -9,8 +27,11 @@ pub struct BasicSynthesizer<G: Generator> {
     pub adsr: AdsrGenerator,
     pub volume: f32,
     pub start_tick: usize,
-    new_note: bool,
+    //note events and the clock they happen at, in order
+    events: VecDeque<(usize, NoteEvent)>,
     pub muted: bool,
+    /// Sources are retriggered with every note and run in note time.
+    pub mod_matrix: ModMatrix<SynthDestination>,
 }
 
 impl<G: Generator> BasicSynthesizer<G> {
@@ -20,29 +41,130 @@ impl<G: Generator> BasicSynthesizer<G> {
             adsr,
             volume,
             start_tick: 0,
-            new_note: false,
+            events: VecDeque::new(),
             muted: true,
+            mod_matrix: ModMatrix::new(),
         }
     }
 
+    /// Plays a note with the next sample, holding the sustain level for `sustain` seconds.
     pub fn play(&mut self, sustain: f32) {
-        self.adsr.sustain = sustain;
-        self.new_note = true;
+        self.play_at(0, sustain);
     }
-}
 
-impl<G: Generator> Patch for BasicSynthesizer<G> {
-    fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
-        if self.new_note {
+    /// Like [`play`](Self::play), starting at the sample at `clock`, e.g. to schedule notes within the next block.
+    pub fn play_at(&mut self, clock: usize, sustain: f32) {
+        self.schedule(clock, NoteEvent::Play(sustain));
+    }
+
+    /// Plays a note with the next sample, until [`note_off`](Self::note_off) is called.
+    ///
+    /// When called while the previous note is still sounding, the envelope continues from its current level.
+    pub fn note_on(&mut self) {
+        self.note_on_at(0);
+    }
+
+    /// Like [`note_on`](Self::note_on), starting at the sample at `clock`.
+    pub fn note_on_at(&mut self, clock: usize) {
+        self.schedule(clock, NoteEvent::NoteOn);
+    }
+
+    /// Releases the note started by [`note_on`](Self::note_on) with the next sample.
+    pub fn note_off(&mut self) {
+        self.note_off_at(0);
+    }
+
+    /// Like [`note_off`](Self::note_off), releasing at the sample at `clock`.
+    pub fn note_off_at(&mut self, clock: usize) {
+        self.schedule(clock, NoteEvent::NoteOff);
+    }
+
+    //events at past clocks happen with the next sample, in the order they were scheduled
+    fn schedule(&mut self, clock: usize, event: NoteEvent) {
+        let index = self.events.iter().position(|(time, _)| *time > clock);
+        self.events.insert(index.unwrap_or(self.events.len()), (clock, event));
+    }
+
+    fn handle_events(&mut self, sample_timing: &SampleTiming) {
+        while let Some(&(time, event)) = self.events.front() {
+            if time > sample_timing.clock {
+                break;
+            }
+            self.events.pop_front();
+            match event {
+                NoteEvent::Play(sustain) => {
+                    self.adsr.reset();
+                    self.adsr.sustain = sustain;
+                }
+                NoteEvent::NoteOn => self.adsr.gate_on(),
+                NoteEvent::NoteOff => {
+                    self.adsr.gate_off();
+                    continue;
+                }
+            }
             self.start_tick = sample_timing.clock;
-            self.new_note = false;
             self.muted = false;
+            self.base_generator.reset();
+            self.mod_matrix.reset();
+        }
+    }
+
+    fn next_modulated_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        self.mod_matrix.process(sample_timing);
+        let volume = (self.volume + self.mod_matrix.amount(SynthDestination::Volume)).max(0.0);
+        let pitch = self.mod_matrix.amount(SynthDestination::Pitch);
+
+        let frequency = self.base_generator.frequency();
+        if let Some(frequency) = frequency {
+            self.base_generator.set_frequency(frequency * (pitch / 12.0).exp2());
+        }
+        let mut poly_sample = self.base_generator.generate(sample_timing);
+        if let Some(frequency) = frequency {
+            self.base_generator.set_frequency(frequency);
+        }
+
+        poly_sample *= volume;
+        let adsr = self.adsr.generate(sample_timing);
+        poly_sample.apply(&adsr);
+        poly_sample
+    }
+
+    //renders a block without note events in it
+    fn render_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) {
+        if self.muted {
+            for poly_sample in output.iter_mut() {
+                *poly_sample = poly_sample!([0.0]);
+            }
+            return;
         }
 
+        let mut sample_timing = sample_timing - self.start_tick;
+        if !self.mod_matrix.is_empty() {
+            for poly_sample in output.iter_mut() {
+                *poly_sample = self.next_modulated_sample(&sample_timing);
+                sample_timing.tick();
+            }
+            return;
+        }
+        self.base_generator.generate_block(&sample_timing, output);
+        for poly_sample in output.iter_mut() {
+            *poly_sample *= self.volume;
+            let adsr = self.adsr.generate(&sample_timing);
+            poly_sample.apply(&adsr);
+            sample_timing.tick();
+        }
+    }
+}
+
+impl<G: Generator> Patch for BasicSynthesizer<G> {
+    fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        self.handle_events(sample_timing);
         let sample_timing = sample_timing - self.start_tick;
 
         if self.muted {
-            poly_sample!()
+            poly_sample!([0.0])
+        } else if !self.mod_matrix.is_empty() {
+            self.next_modulated_sample(&sample_timing)
         } else {
             let mut poly_sample = self.base_generator.generate(&sample_timing);
 
@@ -54,6 +176,33 @@ impl<G: Generator> Patch for BasicSynthesizer<G> {
             poly_sample
         }
     }
+
+    fn next_block(&mut self, sample_timing: &SampleTiming, output: &mut [PolySample]) -> usize {
+        //split at note events, so notes start at the same sample as with `next_sample`
+        let len = output.len();
+        let mut sample_timing = *sample_timing;
+        let mut rest = output;
+        while !rest.is_empty() {
+            self.handle_events(&sample_timing);
+            let length = match self.events.front() {
+                Some((time, _)) => (time - sample_timing.clock).min(rest.len()),
+                None => rest.len(),
+            };
+            let (block, next) = rest.split_at_mut(length);
+            self.render_block(&sample_timing, block);
+            sample_timing.clock += length;
+            rest = next;
+        }
+        len
+    }
+
+    fn state(&self) -> PatchState {
+        if self.muted {
+            PatchState::Silent
+        } else {
+            PatchState::Running
+        }
+    }
 }
 
 impl<G: Generator + Default> Default for BasicSynthesizer<G> {
@@ -63,8 +212,9 @@ impl<G: Generator + Default> Default for BasicSynthesizer<G> {
             adsr: Default::default(),
             volume: 0.1,
             start_tick: 0,
-            new_note: false,
+            events: VecDeque::new(),
             muted: true,
+            mod_matrix: ModMatrix::new(),
         }
     }
 }
@@ -1,3 +1,10 @@
 pub mod basic_synthesizer;
+pub mod poly_synthesizer;
 
-pub use basic_synthesizer::BasicSynthesizer;
+pub use basic_synthesizer::{BasicSynthesizer, SynthDestination};
+pub use poly_synthesizer::{PolySynthesizer, VoiceStealing};
+
+/// Converts a MIDI note number to its frequency, where note 69 is A4 at 440 Hz.
+pub fn midi_to_frequency(note: u8) -> f32 {
+    2.0f32.powf((note as f32 - 69.0) / 12.0) * 440.0
+}
@@ -0,0 +1,164 @@
+use super::midi_to_frequency;
+use crate::{
+    generator::{AdsrGenerator, Generator},
+    prelude::*,
+};
+
+/// Decides which voice is reused when all voices are busy.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VoiceStealing {
+    /// Steals the voice that started playing first.
+    Oldest,
+    /// Steals the voice with the lowest envelope level.
+    Quietest,
+    /// Retriggers the voice already playing the same note, otherwise steals the oldest voice.
+    SameNote,
+}
+
+#[derive(Clone)]
+struct Voice<G: Generator> {
+    generator: G,
+    adsr: AdsrGenerator,
+    /// Note of the voice until its envelope finished, also while releasing.
+    note: Option<u8>,
+    held: bool,
+    velocity: f32,
+    age: u64,
+}
+
+/// Plays multiple notes at once, each on its own voice with a separate generator and envelope.
+#[derive(Clone)]
+pub struct PolySynthesizer<G: Generator + Clone> {
+    voices: Vec<Voice<G>>,
+    pub volume: f32,
+    pub voice_stealing: VoiceStealing,
+    note_count: u64,
+}
+
+impl<G: Generator + Clone> PolySynthesizer<G> {
+    pub fn new(base_generator: G, adsr: AdsrGenerator, voice_count: usize, volume: f32) -> Self {
+        let voice = Voice {
+            generator: base_generator,
+            adsr,
+            note: None,
+            held: false,
+            velocity: 0.0,
+            age: 0,
+        };
+        PolySynthesizer {
+            voices: vec![voice; voice_count],
+            volume,
+            voice_stealing: VoiceStealing::Oldest,
+            note_count: 0,
+        }
+    }
+
+    /// Starts playing the MIDI `note` with `velocity` from `0.0` to `1.0` on a free or stolen voice.
+    pub fn note_on(&mut self, note: u8, velocity: f32) {
+        let index = match self.allocate_voice(note) {
+            Some(index) => index,
+            None => return,
+        };
+        self.note_count += 1;
+
+        let voice = &mut self.voices[index];
+        if !voice.adsr.is_active() {
+            voice.generator.reset();
+        }
+        voice.generator.set_frequency(midi_to_frequency(note));
+        voice.adsr.gate_on();
+        voice.note = Some(note);
+        voice.held = true;
+        voice.velocity = velocity;
+        voice.age = self.note_count;
+    }
+
+    /// Releases all voices playing the MIDI `note`.
+    pub fn note_off(&mut self, note: u8) {
+        for voice in &mut self.voices {
+            if voice.held && voice.note == Some(note) {
+                voice.adsr.gate_off();
+                voice.held = false;
+            }
+        }
+    }
+
+    pub fn all_notes_off(&mut self) {
+        for voice in &mut self.voices {
+            voice.adsr.gate_off();
+            voice.held = false;
+        }
+    }
+
+    /// Count of voices that are playing or releasing a note.
+    pub fn active_voice_count(&self) -> usize {
+        self.voices.iter().filter(|voice| voice.adsr.is_active()).count()
+    }
+
+    /// Applies `f` to the generators of all voices, e.g. to change parameters other than the frequency.
+    pub fn for_each_generator<F: FnMut(&mut G)>(&mut self, mut f: F) {
+        for voice in &mut self.voices {
+            f(&mut voice.generator);
+        }
+    }
+
+    /// Applies `f` to the envelopes of all voices.
+    pub fn for_each_adsr<F: FnMut(&mut AdsrGenerator)>(&mut self, mut f: F) {
+        for voice in &mut self.voices {
+            f(&mut voice.adsr);
+        }
+    }
+
+    fn allocate_voice(&self, note: u8) -> Option<usize> {
+        let voices = self.voices.iter().enumerate();
+        if self.voice_stealing == VoiceStealing::SameNote {
+            let same_note = voices
+                .clone()
+                .find(|(_, voice)| voice.adsr.is_active() && voice.note == Some(note));
+            if let Some((index, _)) = same_note {
+                return Some(index);
+            }
+        }
+        if let Some((index, _)) = voices.clone().find(|(_, voice)| !voice.adsr.is_active()) {
+            return Some(index);
+        }
+        match self.voice_stealing {
+            VoiceStealing::Oldest | VoiceStealing::SameNote => {
+                voices.min_by_key(|(_, voice)| voice.age).map(|(index, _)| index)
+            }
+            VoiceStealing::Quietest => voices
+                .min_by(|(_, a), (_, b)| a.adsr.level().total_cmp(&b.adsr.level()))
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+impl<G: Generator + Clone> Patch for PolySynthesizer<G> {
+    fn next_sample(&mut self, sample_timing: &SampleTiming) -> PolySample {
+        let mut poly_sample = poly_sample!([0.0]);
+        for voice in &mut self.voices {
+            if !voice.adsr.is_active() {
+                continue;
+            }
+            let adsr = voice.adsr.generate(sample_timing)[0];
+            let voice_sample =
+                voice.generator.generate(sample_timing) * (adsr * voice.velocity * self.volume);
+            poly_sample.mix(&voice_sample);
+        }
+        poly_sample
+    }
+
+    fn state(&self) -> PatchState {
+        if self.active_voice_count() == 0 {
+            PatchState::Silent
+        } else {
+            PatchState::Running
+        }
+    }
+}
+
+impl<G: Generator + Clone + Default> Default for PolySynthesizer<G> {
+    fn default() -> Self {
+        Self::new(G::default(), Default::default(), 8, 0.1)
+    }
+}
@@ -0,0 +1,31 @@
+use crate::PolySample;
+use anyhow::Result;
+use hound::{SampleFormat, WavReader};
+use std::path::Path;
+
+/// Reads all frames of a PCM or float WAV file, dropping channels that don't fit into a [`PolySample`].
+///
+/// Returns the frames and the sample rate of the file.
+pub(crate) fn read_wav<P: AsRef<Path>>(path: P) -> Result<(Vec<PolySample>, f32)> {
+    let mut reader = WavReader::open(path)?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+
+    let samples = match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 * scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+
+    let frames = samples
+        .chunks_exact(channels)
+        .map(|frame| PolySample(frame.iter().copied().take(PolySample::MAX_CHANNELS).collect()))
+        .collect();
+
+    Ok((frames, spec.sample_rate as f32))
+}